@@ -0,0 +1,96 @@
+use gloo_timers::callback::Timeout;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use yew::functional::*;
+use yew::prelude::*;
+
+use crate::services::settings::{AvatarShape, SettingsContext};
+use crate::utils::url::with_query_param;
+
+const MAX_AVATAR_RETRIES: u32 = 2;
+const AVATAR_RETRY_BASE_MS: u32 = 500;
+
+thread_local! {
+    // Failed load attempts per avatar URL, shared by every `Avatar` so a URL
+    // that has already given up renders initials straight away.
+    static AVATAR_FAILURES: RefCell<HashMap<String, u32>> = RefCell::new(HashMap::new());
+}
+
+fn failures(src: &str) -> u32 {
+    AVATAR_FAILURES.with(|f| f.borrow().get(src).copied().unwrap_or(0))
+}
+
+fn record_failure(src: &str) -> u32 {
+    AVATAR_FAILURES.with(|f| {
+        let mut failures = f.borrow_mut();
+        let count = failures.entry(src.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    })
+}
+
+/// Up to two uppercase initials taken from the words of `name`.
+pub fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AvatarProps {
+    pub src: String,
+    pub name: String,
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+#[function_component(Avatar)]
+pub fn avatar(props: &AvatarProps) -> Html {
+    let attempt = use_state(|| failures(&props.src));
+    let retry = use_mut_ref(|| None::<Timeout>);
+    let settings = use_context::<SettingsContext>();
+    // Text-only mode never requests the image at all.
+    let show_image = settings.as_ref().is_none_or(|settings| settings.show_avatars);
+    let class = classes!(
+        props.class.clone(),
+        settings.map_or(AvatarShape::Circle, |settings| settings.avatar_shape).class(),
+    );
+
+    if !show_image || *attempt > MAX_AVATAR_RETRIES {
+        return html! {
+            <div
+                title={props.name.clone()}
+                class={classes!(class, "flex", "items-center", "justify-center", "bg-gray-300", "text-gray-700", "text-xs", "font-semibold")}
+            >
+                {initials(&props.name)}
+            </div>
+        };
+    }
+
+    let onerror = {
+        let src = props.src.clone();
+        let attempt = attempt.clone();
+        Callback::from(move |_: Event| {
+            let failed = record_failure(&src);
+            if failed > MAX_AVATAR_RETRIES {
+                attempt.set(failed);
+                return;
+            }
+            let attempt = attempt.clone();
+            let delay = AVATAR_RETRY_BASE_MS << (failed - 1);
+            *retry.borrow_mut() = Some(Timeout::new(delay, move || attempt.set(failed)));
+        })
+    };
+    // A changing query string makes the browser request the image again.
+    let src = match *attempt {
+        0 => props.src.clone(),
+        n => with_query_param(&props.src, "retry", &n.to_string()),
+    };
+
+    // Off-screen avatars in a long roster or history load as they scroll in.
+    html! {
+        <img {class} {src} {onerror} alt={props.name.clone()} loading="lazy" decoding="async" />
+    }
+}
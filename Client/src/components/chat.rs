@@ -1,216 +1,3096 @@
-use serde::{Deserialize, Serialize};
-use web_sys::HtmlInputElement;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use gloo_events::EventListener;
+use gloo_timers::callback::{Interval, Timeout};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{BeforeUnloadEvent, CustomEvent, CustomEventInit, Element, HtmlInputElement, HtmlTextAreaElement, KeyboardEvent};
 use yew::prelude::*;
-use yew_agent::{Bridge, Bridged};
+use yew::context::ContextHandle;
+use yew::html::Scope;
+
+use crate::{User, services::websocket::{ConnectionState, ExponentialBackoff, Jittered, ReconnectStrategy, WebsocketService, WsError, DEFAULT_MAX_RECONNECTS, DEFAULT_RECONNECT_JITTER}};
+use crate::components::avatar::Avatar;
+use crate::components::code_block::CodeBlock;
+use crate::components::commands::{CommandContext, CommandOutput, Commands};
+use crate::components::emoji_picker::EmojiPicker;
+use crate::components::render::{plain_text, Content, RenderContext, Rendered, Renderers};
+use crate::components::settings::SettingsPanel;
+use crate::services::clipboard;
+use crate::services::codec::{self, Codec};
+use crate::services::download;
+use crate::services::event_bus::{self, Subscription};
+use crate::services::metrics;
+use crate::services::read_marker;
+use crate::services::scroll_position::{self, ScrollPosition};
+use crate::services::sound;
+use crate::services::toast::{self, Severity};
+use crate::services::protocol::{Category, ErrorData, MessageData, MessageEdit, MessageReply, MsgTypes, RosterEntry, WebSocketMessage};
+use crate::services::settings::{ImageSize, SendKey, Settings, SettingsContext, TimestampDisplay};
+use crate::services::theme::Theme;
+use crate::state::chat::{ChatState, Presence, Seed, UserProfile, DEFAULT_DUPLICATE_WINDOW_MS};
+use crate::state::composer::{Composer, Submission};
+use crate::state::outbox::Outbox;
+use crate::utils::code::{code_blocks, Chunk};
+use crate::utils::color::username_color;
+use crate::utils::dicebear::DicebearVersion;
+use crate::utils::export::{self, ExportFormat};
+use crate::utils::format::{insert_at, wrap_selection, Style};
+use crate::utils::fuzzy;
+use crate::utils::latency::Latency;
+use crate::utils::quote::{blocks, quote};
+use crate::utils::time::{day_breaks, format_countdown, format_date, format_date_time, format_time, local_day};
+use crate::utils::tokenize::{mentions, mentions_everyone, pending_mention, tokenize};
+use crate::utils::typing::{typing_summary, TypingSignal};
+use crate::utils::url::{anchored_message, download_name, file_name, is_http_url, is_image_url, message_anchor};
+use crate::utils::username::validate_username;
+use crate::utils::word_filter::WordFilter;
+
+// Where to put the message list's scrollbar after the next render.
+enum ScrollRestore {
+    // The user was following the conversation; keep showing the newest message.
+    Bottom,
+    // Keep the same offset from the top.
+    Top(i32),
+    // Keep the same offset from the bottom, for content added above.
+    FromBottom(i32),
+}
+
+// How long to wait for the server to echo a sent message before sending it
+// again, or after the last resend, giving up.
+const SEND_CONFIRM_MS: u32 = 10_000;
+
+// How long the input may sit untouched before we tell others we stopped typing.
+const TYPING_IDLE_MS: u32 = 3_000;
+
+// How often to ping the server while connected, to measure latency.
+const PING_INTERVAL_MS: u32 = 15_000;
+
+// Matches the presence animations in static/index.html.
+const PRESENCE_TRANSITION_MS: u32 = 300;
+
+// How long a message jumped to from a pin or a link stays highlighted.
+const HIGHLIGHT_MS: u32 = 3_000;
+
+// The server has a single room; this names it in the header and in exports.
+const ROOM_NAME: &str = "Chat Room";
+// Names it in what is kept in browser storage, where the name could change.
+const ROOM_ID: &str = "main";
+
+/// People listed in the sidebar before the rest collapse.
+pub const DEFAULT_ROSTER_LIMIT: usize = 50;
+
+/// How long without input before we show as away.
+pub const DEFAULT_IDLE_MS: u32 = 5 * 60_000;
+
+// Activity closer together than this doesn't restart the idle timer.
+const ACTIVITY_THROTTLE_MS: f64 = 1_000.0;
+
+// How many people the mention suggestions offer at once.
+const MENTION_SUGGESTIONS: usize = 5;
+
+// Avatars in a "seen by" stack before the rest collapse into "+N".
+const SEEN_BY_AVATARS: usize = 3;
+
+// What the disappearing messages button steps through: how long messages
+// we send stay up, with a short and a long name.
+const DISAPPEAR_AFTER: [(Option<u32>, &str, &str); 4] = [
+    (None, "", "Off"),
+    (Some(60_000), "1m", "1 minute"),
+    (Some(3_600_000), "1h", "1 hour"),
+    (Some(86_400_000), "1d", "1 day"),
+];
 
-use crate::{User, services::websocket::WebsocketService};
-use crate::services::event_bus::EventBus;
+/// The quick replies offered when `ChatProps::quick_replies` is unset.
+pub const DEFAULT_QUICK_REPLIES: [&str; 3] = ["👍", "On my way", "Thanks!"];
 
+fn default_quick_replies() -> Vec<String> {
+    DEFAULT_QUICK_REPLIES.iter().map(|&reply| reply.into()).collect()
+}
+
+/// What an empty room says when `ChatProps::empty_message` is unset.
+pub const DEFAULT_EMPTY_MESSAGE: &str = "No messages yet. Say hi! 👋";
+
+fn default_empty_message() -> String {
+    DEFAULT_EMPTY_MESSAGE.into()
+}
+
+#[allow(clippy::enum_variant_names)]
 pub enum Msg {
-    HandleMsg(String),
+    HandleMsg(WebSocketMessage),
     SubmitMessage,
+    ToggleSettings,
+    ToggleExportMenu,
+    Export(ExportFormat),
+    SettingsChanged(SettingsContext),
+    VisibilityChanged,
+    MessagesScrolled,
+    MarkAllRead,
+    InputChanged(String),
+    TypingIdle,
+    /// Keyboard, mouse or touch input anywhere on the page.
+    Activity,
+    Idle,
+    FocusComposer,
+    /// Steps to the next `DISAPPEAR_AFTER` duration.
+    CycleDisappearAfter,
+    /// Removes disappearing messages that are due and moves the countdowns on.
+    ExpiryTick,
+    StartEdit(String),
+    DeleteMessage(String),
+    CancelEdit,
+    SubmitUsername,
+    ToggleMetrics,
+    RefreshMetrics,
+    ConnectionChanged(ConnectionState),
+    ReconnectNow,
+    CountdownTick,
+    Heartbeat,
+    PresenceSettled,
+    SendTimedOut,
+    JumpToLatest,
+    ToggleSidebar,
+    /// The viewport crossed the narrow screen breakpoint.
+    LayoutChanged,
+    FilterRoster(String),
+    ToggleRosterExpanded,
+    /// Finishes the mention being typed with this username.
+    CompleteMention(String),
+    TogglePin(String),
+    TogglePinnedBar,
+    /// Moves a pin to an index in `ChatState::pinned` and shares the order.
+    MovePin(String, usize),
+    /// A pin started or stopped being dragged in the pinned list.
+    DragPin(Option<String>),
+    /// The dragged pin was dropped onto this one, taking its place.
+    DropPin(String),
+    ShowMessage(String),
+    CopyLink(String),
+    Quote(String),
+    /// A message sent without the composer, from a quick reply or
+    /// `ChatHandle::send`.
+    SendText(String),
+    /// Opens the thread rooted at a message in the side panel.
+    OpenThread(String),
+    CloseThread,
+    ThreadInput(String),
+    SubmitReply,
+    /// Wraps the composer's selection in markdown for a toolbar button.
+    Format(Style),
+    ToggleAttach,
+    ToggleEmojiPicker,
+    /// Puts an emoji from the picker at the composer's cursor.
+    InsertEmoji(&'static str),
+    AddAttachment,
+    RemoveAttachment(usize),
+    ClearHighlight,
+    /// Shows or hides the raw body of a message with unsupported content.
+    ToggleRaw(String),
+    DownloadRaw(String),
+    OpenImage(String),
+    CloseImage,
+    /// Fetches an inline image and saves it locally.
+    DownloadImage(String),
 }
 
-#[derive(Deserialize)]
-struct MessageData {
-    from: String,
-    message: String,
+#[derive(Properties)]
+pub struct ChatProps {
+    /// Called with every chat message received from the server, including
+    /// our own echoes, for apps that embed the chat and want to react to it.
+    #[prop_or_default]
+    pub on_message: Option<Callback<MessageData>>,
+    /// Also dispatches every received chat message on `window` as a
+    /// `chat:message` CustomEvent, with the message as its `detail`, for
+    /// scripts outside the Yew app.
+    #[prop_or_default]
+    pub dom_events: bool,
+    /// Shows the room without the composer or message actions, e.g. for a
+    /// live feed. No username is needed.
+    #[prop_or_default]
+    pub read_only: bool,
+    /// Whether a read-only client that has a username still registers and
+    /// so appears in the roster. Spectators stay hidden by default.
+    #[prop_or_default]
+    pub register_spectator: bool,
+    /// Failed reconnects in a row before the chat stops retrying and waits
+    /// for the user. `None` never gives up.
+    #[prop_or(Some(DEFAULT_MAX_RECONNECTS))]
+    pub max_reconnects: Option<u32>,
+    /// Whether coming back online or to the tab while disconnected retries
+    /// straight away instead of waiting for the next backoff attempt.
+    #[prop_or(true)]
+    pub reconnect_on_wake: bool,
+    /// How far either way each reconnect delay is randomly moved, as a
+    /// fraction of it, so many clients don't reconnect in lockstep after a
+    /// server restart. `0.0` turns it off. Unused with `reconnect_strategy`.
+    #[prop_or(DEFAULT_RECONNECT_JITTER)]
+    pub reconnect_jitter: f64,
+    /// The wait before each reconnect attempt; exponential backoff with
+    /// `reconnect_jitter` when unset.
+    #[prop_or_default]
+    pub reconnect_strategy: Option<Rc<dyn ReconnectStrategy>>,
+    /// Whether messages sent while disconnected wait in the outgoing queue
+    /// until the connection is back. Off, sending is disabled until then.
+    #[prop_or(true)]
+    pub queue_offline: bool,
+    /// How many people the sidebar lists before collapsing the rest behind
+    /// an "and N more…" button. `None` lists everyone.
+    #[prop_or(Some(DEFAULT_ROSTER_LIMIT))]
+    pub roster_limit: Option<usize>,
+    /// Milliseconds without keyboard, mouse or touch input before others
+    /// see us as away. `None` never goes away.
+    #[prop_or(Some(DEFAULT_IDLE_MS))]
+    pub idle_after_ms: Option<u32>,
+    /// Identical messages a user sends within this many milliseconds of each
+    /// other are shown once with a count. `None` shows every copy.
+    #[prop_or(Some(DEFAULT_DUPLICATE_WINDOW_MS))]
+    pub duplicate_window_ms: Option<u32>,
+    /// Where generated avatars come from. The default is the current
+    /// DiceBear API; `V6` keeps the old URLs for self-hosted mirrors.
+    #[prop_or_default]
+    pub avatar_api: DicebearVersion,
+    /// Colours for embedding apps to match their branding.
+    #[prop_or_default]
+    pub theme: Theme,
+    /// How message text is rendered; `Renderers::default()` when unset.
+    #[prop_or_default]
+    pub renderers: Option<Rc<Renderers>>,
+    /// Slash commands handled in the browser before anything is sent; see
+    /// `Commands`.
+    #[prop_or_default]
+    pub commands: Option<Rc<Commands>>,
+    /// Users and messages to show from the start, e.g. for tests and demos.
+    /// Live frames then apply on top. A seeded chat doesn't load the latest
+    /// history page on connecting, but still pages back from its oldest
+    /// message.
+    #[prop_or_default]
+    pub seed: Option<Rc<Seed>>,
+    /// One-tap replies shown above an empty composer. An empty list hides
+    /// the row.
+    #[prop_or_else(default_quick_replies)]
+    pub quick_replies: Vec<String>,
+    /// Shown in place of the message list while the room has no messages.
+    #[prop_or_else(default_empty_message)]
+    pub empty_message: String,
+    /// Label of a button under `empty_message` that puts the cursor in the
+    /// composer. `None` shows no button.
+    #[prop_or_default]
+    pub empty_action: Option<String>,
+    /// Called once, as the chat is created, with a handle for sending
+    /// messages from code.
+    #[prop_or_default]
+    pub on_ready: Option<Callback<ChatHandle>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "lowercase")]
-pub enum MsgTypes {
-    Users,
-    Register,
-    Message,
+impl PartialEq for ChatProps {
+    fn eq(&self, other: &Self) -> bool {
+        // Destructured so a new prop can't be left out of the comparison.
+        let ChatProps {
+            on_message,
+            dom_events,
+            read_only,
+            register_spectator,
+            max_reconnects,
+            reconnect_on_wake,
+            reconnect_jitter,
+            reconnect_strategy,
+            queue_offline,
+            roster_limit,
+            idle_after_ms,
+            duplicate_window_ms,
+            avatar_api,
+            theme,
+            renderers,
+            commands,
+            quick_replies,
+            empty_message,
+            empty_action,
+            on_ready,
+            seed,
+        } = self;
+        // Seeds can be large, so only a different one counts as a change.
+        let same_seed = match (seed, &other.seed) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        same_seed
+            && on_message == &other.on_message
+            && dom_events == &other.dom_events
+            && read_only == &other.read_only
+            && register_spectator == &other.register_spectator
+            && max_reconnects == &other.max_reconnects
+            && reconnect_on_wake == &other.reconnect_on_wake
+            && reconnect_jitter == &other.reconnect_jitter
+            && reconnect_strategy == &other.reconnect_strategy
+            && queue_offline == &other.queue_offline
+            && roster_limit == &other.roster_limit
+            && idle_after_ms == &other.idle_after_ms
+            && duplicate_window_ms == &other.duplicate_window_ms
+            && avatar_api == &other.avatar_api
+            && theme == &other.theme
+            && renderers == &other.renderers
+            && commands == &other.commands
+            && quick_replies == &other.quick_replies
+            && empty_message == &other.empty_message
+            && empty_action == &other.empty_action
+            && on_ready == &other.on_ready
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WebSocketMessage {
-    message_type: MsgTypes,
-    data_array: Option<Vec<String>>,
-    data: Option<String>,
+/// Sends chat messages on behalf of the host app, e.g. for bots or quick
+/// replies. Messages go through the same checks and queue as typed ones.
+///
+/// The handle belongs to the `Chat` that created it: messages sent after
+/// that component is destroyed are dropped, and a new `Chat` hands out a
+/// new handle.
+#[derive(Clone)]
+pub struct ChatHandle {
+    link: Scope<Chat>,
 }
 
-#[derive(Clone)]
-struct UserProfile {
-    name: String,
-    avatar: String,
+impl ChatHandle {
+    /// Sends `text` as a new message from the signed-in user. Blank text is
+    /// ignored, as is anything sent before the user has joined or while the
+    /// chat is read-only.
+    pub fn send(&self, text: impl Into<String>) {
+        self.link.send_message(Msg::SendText(text.into()));
+    }
 }
 
 pub struct Chat {
-    users: Vec<UserProfile>,
+    user: User,
+    state: ChatState,
     chat_input: NodeRef,
+    // Set once the composer has been focused after first appearing.
+    autofocused: bool,
+    username_input: NodeRef,
+    username_error: Option<&'static str>,
+    messages_container: NodeRef,
+    // Whether a `history` request is waiting for its page.
+    loading_history: bool,
+    // Whether the first roster or history page has arrived. Until then the
+    // panels show placeholders instead of looking empty.
+    initial_data: bool,
+    // Captured before every update, since yew 0.19 has no
+    // `get_snapshot_before_update`, and applied in `rendered`.
+    scroll_restore: Option<ScrollRestore>,
     wss: WebsocketService,
-    messages: Vec<MessageData>,
-    _producer: Box<dyn Bridge<EventBus>>,
+    codec: Box<dyn Codec>,
+    connection: ConnectionState,
+    // When the next reconnect attempt is due, while recovering from a drop.
+    reconnect_at: Option<f64>,
+    countdown: Option<Interval>,
+    // Index into `DISAPPEAR_AFTER` for the messages we send.
+    disappear_after: usize,
+    // Ticks while any loaded message is set to disappear.
+    expiry_tick: Option<Interval>,
+    // Sends pings while the socket is open.
+    heartbeat: Option<Interval>,
+    latency: Latency,
+    presence_timeout: Option<Timeout>,
+    renderers: Rc<Renderers>,
+    settings: SettingsContext,
+    // Built from the settings' word list while the filter is on.
+    word_filter: Option<WordFilter>,
+    show_settings: bool,
+    show_export_menu: bool,
+    composer: Composer,
+    // Whether the field for adding an attachment URL is open.
+    attaching: bool,
+    // Whether the emoji picker is open above the composer.
+    emoji_picker: bool,
+    attachment_input: NodeRef,
+    attachment_error: bool,
+    // The root of the thread open in the side panel, and its reply box.
+    thread: Option<String>,
+    thread_text: String,
+    thread_input: NodeRef,
+    sidebar_open: bool,
+    roster_filter: String,
+    // Whether the sidebar shows everyone past `ChatProps::roster_limit`.
+    roster_expanded: bool,
+    // Messages with unsupported content whose raw body is shown.
+    raw_shown: HashSet<String>,
+    show_pinned: bool,
+    dragged_pin: Option<String>,
+    // The read marker as last saved to storage.
+    saved_read: Option<f64>,
+    // The message our last read receipt pointed at on this connection.
+    sent_receipt: Option<String>,
+    typing: TypingSignal,
+    typing_timeout: Option<Timeout>,
+    // Whether we've told the server we're away.
+    idle: bool,
+    idle_timeout: Option<Timeout>,
+    last_activity: f64,
+    // Messages sent but not yet echoed back by the server, and the timer for
+    // the next one to run out of time.
+    outbox: Outbox,
+    send_timeout: Option<Timeout>,
+    // Message linked from the page URL, shown once it has been loaded.
+    anchor: Option<String>,
+    // Where the message list was left last time, until it has been loaded
+    // and scrolled back to; and the position last written to storage.
+    restore_position: Option<ScrollPosition>,
+    saved_position: Option<ScrollPosition>,
+    highlighted: Option<String>,
+    highlight_timeout: Option<Timeout>,
+    // Image shown full size over the chat.
+    lightbox: Option<String>,
+    // Keeps the metrics overlay ticking while it is open.
+    metrics_refresh: Option<Interval>,
+    // Present only while the composer holds unsent text.
+    unload_guard: Option<EventListener>,
+    _frames: Subscription,
+    _visibility_listener: EventListener,
+    _settings_listener: ContextHandle<SettingsContext>,
+    _keydown_listener: EventListener,
+    _activity_listeners: Vec<EventListener>,
+    // Below the `md` breakpoint the user list is a bottom sheet over the chat.
+    narrow: bool,
+    _layout_listener: Option<EventListener>,
 }
 impl Component for Chat {
     type Message = Msg;
-    type Properties = ();
+    type Properties = ChatProps;
 
     fn create(ctx: &Context<Self>) -> Self {
         let (user, _) = ctx
             .link()
             .context::<User>(Callback::noop())
             .expect("context to be set");
-        let wss = WebsocketService::new();
-        let username = user.username.borrow().clone();
+        let (settings, settings_listener) = ctx
+            .link()
+            .context::<SettingsContext>(ctx.link().callback(Msg::SettingsChanged))
+            .expect("settings context to be set");
+        let strategy = ctx.props().reconnect_strategy.clone().unwrap_or_else(|| {
+            let seed = (js_sys::Math::random() * u64::MAX as f64) as u64;
+            Rc::new(Jittered::new(ExponentialBackoff::default(), ctx.props().reconnect_jitter, seed))
+        });
+        let wss = WebsocketService::new(
+            ctx.link().callback(Msg::ConnectionChanged),
+            ctx.props().max_reconnects,
+            ctx.props().reconnect_on_wake,
+            strategy,
+        );
+        // Opening /chat directly skips the login screen, so the name may still
+        // be unset; the view then asks for one before registering.
+        let username = validate_username(&user.username.borrow()).unwrap_or_default();
 
-        let message = WebSocketMessage {
-            message_type: MsgTypes::Register,
-            data: Some(username.to_string()),
-            data_array: None,
+        let visibility_listener = {
+            let link = ctx.link().clone();
+            EventListener::new(&gloo_utils::document(), "visibilitychange", move |_| {
+                link.send_message(Msg::VisibilityChanged)
+            })
+        };
+
+        let layout_listener = gloo_utils::window()
+            .match_media(NARROW_SCREEN)
+            .ok()
+            .flatten()
+            .map(|query| {
+                let link = ctx.link().clone();
+                EventListener::new(&query, "change", move |_| link.send_message(Msg::LayoutChanged))
+            });
+
+        let keydown_listener = {
+            let link = ctx.link().clone();
+            EventListener::new(&gloo_utils::document(), "keydown", move |e| {
+                let e = e.dyn_ref::<KeyboardEvent>().unwrap();
+                if e.ctrl_key() && e.shift_key() && e.key().eq_ignore_ascii_case("m") {
+                    link.send_message(Msg::ToggleMetrics)
+                }
+                // End jumps to the newest message, except inside text fields
+                // where it moves the caret; Ctrl/Cmd+End works everywhere.
+                if e.key() == "End" && (e.ctrl_key() || e.meta_key() || !is_editing_text(e)) {
+                    e.prevent_default();
+                    link.send_message(Msg::JumpToLatest)
+                }
+                if e.key() == "Escape" {
+                    link.send_message(Msg::CloseImage);
+                    link.send_message(Msg::CloseThread)
+                }
+            })
         };
 
-        if let Ok(_) = wss
-            .tx
-            .clone()
-            .try_send(serde_json::to_string(&message).unwrap())
-        {
-            log::debug!("message sent successfully");
+        if let Some(on_ready) = &ctx.props().on_ready {
+            on_ready.emit(ChatHandle { link: ctx.link().clone() });
+        }
+
+        let activity_listeners = ["keydown", "pointerdown", "pointermove", "wheel", "touchstart"]
+            .iter()
+            .map(|&event| {
+                let link = ctx.link().clone();
+                EventListener::new(&gloo_utils::document(), event, move |_| link.send_message(Msg::Activity))
+            })
+            .collect();
+        ctx.link().send_message(Msg::Activity);
+
+        // A linked message beats picking up where we left off.
+        let anchor = gloo_utils::window()
+            .location()
+            .hash()
+            .ok()
+            .and_then(|hash| anchored_message(&hash).map(String::from));
+
+        let mut state = ChatState::new(username);
+        state.last_read = read_marker::load(&state.username);
+        state.duplicate_window_ms = ctx.props().duplicate_window_ms;
+        state.avatar_api = ctx.props().avatar_api;
+        let seeded = ctx.props().seed.is_some();
+        if let Some(seed) = &ctx.props().seed {
+            state.seed(Seed::clone(seed));
         }
 
         Self {
-            users: vec![],
-            messages: vec![],
+            user,
+            state,
             chat_input: NodeRef::default(),
+            autofocused: false,
+            username_input: NodeRef::default(),
+            username_error: None,
+            messages_container: NodeRef::default(),
+            loading_history: false,
+            initial_data: seeded,
+            scroll_restore: seeded.then_some(ScrollRestore::Bottom),
             wss,
-            _producer: EventBus::bridge(ctx.link().callback(Msg::HandleMsg)),
+            codec: codec::configured(),
+            connection: ConnectionState::Connecting,
+            reconnect_at: None,
+            countdown: None,
+            disappear_after: 0,
+            expiry_tick: None,
+            heartbeat: None,
+            latency: Latency::default(),
+            presence_timeout: None,
+            renderers: ctx.props().renderers.clone().unwrap_or_default(),
+            word_filter: word_filter(&settings),
+            settings,
+            show_settings: false,
+            show_export_menu: false,
+            composer: Composer::default(),
+            attaching: false,
+            emoji_picker: false,
+            attachment_input: NodeRef::default(),
+            attachment_error: false,
+            thread: None,
+            thread_text: String::new(),
+            thread_input: NodeRef::default(),
+            sidebar_open: !is_narrow_screen(),
+            roster_filter: String::new(),
+            roster_expanded: false,
+            raw_shown: HashSet::new(),
+            show_pinned: false,
+            dragged_pin: None,
+            saved_read: None,
+            sent_receipt: None,
+            typing: TypingSignal::default(),
+            typing_timeout: None,
+            idle: false,
+            idle_timeout: None,
+            last_activity: 0.0,
+            outbox: Outbox::new(format!("{:x}", (js_sys::Math::random() * u32::MAX as f64) as u32)),
+            send_timeout: None,
+            restore_position: if anchor.is_none() { scroll_position::load(ROOM_ID) } else { None },
+            anchor,
+            saved_position: None,
+            highlighted: None,
+            highlight_timeout: None,
+            lightbox: None,
+            metrics_refresh: None,
+            unload_guard: None,
+            _frames: event_bus::subscribe(ctx.link().callback(Msg::HandleMsg)),
+            _visibility_listener: visibility_listener,
+            _settings_listener: settings_listener,
+            _keydown_listener: keydown_listener,
+            _activity_listeners: activity_listeners,
+            narrow: is_narrow_screen(),
+            _layout_listener: layout_listener,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        // The DOM only changes when we render, so the latest capture before a
+        // render is the one to restore.
+        self.scroll_restore = self.messages_container.cast::<Element>().map(|el| {
+            if is_scrolled_to_bottom(&el) {
+                ScrollRestore::Bottom
+            } else {
+                ScrollRestore::Top(el.scroll_top())
+            }
+        });
         match msg {
-            Msg::HandleMsg(s) => {
-                let msg: WebSocketMessage = serde_json::from_str(&s).unwrap();
+            Msg::HandleMsg(msg) => {
+                if matches!(msg.message_type, MsgTypes::Users | MsgTypes::History) {
+                    self.initial_data = true;
+                }
                 match msg.message_type {
+                    MsgTypes::History => {}
                     MsgTypes::Users => {
-                        let users_from_message = msg.data_array.unwrap_or_default();
-                        self.users = users_from_message
-                            .iter()
-                            .map(|u| UserProfile {
-                                name: u.into(),
-                                avatar: format!(
-                                    "https://avatars.dicebear.com/api/adventurer-neutral/{}.svg",
-                                    u
-                                )
-                                .into(),
-                            })
-                            .collect();
+                        if !self.state.apply(msg) {
+                            return false;
+                        }
+                        if !self.state.joined.is_empty() || !self.state.leaving.is_empty() {
+                            let link = ctx.link().clone();
+                            self.presence_timeout = Some(Timeout::new(PRESENCE_TRANSITION_MS, move || {
+                                link.send_message(Msg::PresenceSettled)
+                            }));
+                        }
                         return true;
                     }
+                    MsgTypes::Pong => {
+                        let id = msg.data.as_deref().and_then(|id| id.parse().ok());
+                        let measured = id.and_then(|id| self.latency.pong(id, js_sys::Date::now()));
+                        let average = self.latency.average();
+                        metrics::record(|m| m.rtt_ms = average);
+                        return measured.is_some() && self.settings.show_latency;
+                    }
+                    MsgTypes::Error => {
+                        let error = msg
+                            .data
+                            .as_deref()
+                            .and_then(|data| serde_json::from_str::<ErrorData>(data).ok());
+                        let taken = matches!(&error, Some(e) if e.code == "username_taken");
+                        let text = match &error {
+                            Some(error) => error.describe(),
+                            None => "The server reported an error.".to_string(),
+                        };
+                        toast::notify(text, Severity::Error);
+                        if taken {
+                            // Back to the username form to pick another name.
+                            self.user.username.borrow_mut().clear();
+                            self.state.username.clear();
+                            return true;
+                        }
+                        // Only a rejected send leaves the outbox; a failed edit
+                        // or delete says nothing about the messages in flight.
+                        let dropped = match &error {
+                            Some(ErrorData { client_id: Some(client_id), .. }) => self.outbox.confirm(client_id),
+                            Some(error) if error.rejects_send() => self.outbox.confirm_oldest(),
+                            _ => false,
+                        };
+                        if dropped {
+                            self.schedule_resend(ctx);
+                        }
+                        return dropped;
+                    }
                     MsgTypes::Message => {
-                        let message_data: MessageData =
-                            serde_json::from_str(&msg.data.unwrap()).unwrap();
-                        self.messages.push(message_data);
+                        // A repeat collapsed into an earlier copy still comes
+                        // back as itself, so its own send gets confirmed.
+                        let entry = match self.state.apply_entry(msg) {
+                            Some(entry) => entry,
+                            None => return false,
+                        };
+                        if let Some(on_message) = &ctx.props().on_message {
+                            on_message.emit(entry.clone());
+                        }
+                        if ctx.props().dom_events {
+                            dispatch_message_event(&entry);
+                        }
+                        let incoming = entry.category == Category::Chat && entry.from != self.state.username;
+                        if incoming && self.state.away && !self.settings.mute_sounds {
+                            sound::play(self.settings.notification_sound);
+                        }
+                        let own = entry.from == self.state.username;
+                        let confirmed = match &entry.client_id {
+                            Some(client_id) => self.outbox.confirm(client_id),
+                            // Servers that don't echo client ids answer in order.
+                            None => own && self.outbox.confirm_oldest(),
+                        };
+                        if confirmed {
+                            self.schedule_resend(ctx);
+                        }
                         return true;
                     }
-                    _ => {
-                        return false;
+                    _ => return self.state.apply(msg),
+                }
+                self.loading_history = false;
+                // Older messages go above the viewport instead of pushing it down.
+                self.scroll_restore = self
+                    .messages_container
+                    .cast::<Element>()
+                    .map(|el| ScrollRestore::FromBottom(el.scroll_height() - el.scroll_top()));
+                self.state.apply(msg);
+                // Keep paging back until the linked message shows up.
+                if let Some(anchor) = &self.anchor {
+                    if self.state.message(anchor).is_none() && !self.request_history() {
+                        self.anchor = None;
+                        toast::notify("The linked message is no longer available.", Severity::Info);
                     }
                 }
+                // Likewise for where we left off, without a fuss if it's gone.
+                if let Some(restore) = &self.restore_position {
+                    if self.state.message(&restore.message_id).is_none() && !self.request_history() {
+                        self.restore_position = None;
+                    }
+                }
+                true
             }
             Msg::SubmitMessage => {
-                let input = self.chat_input.cast::<HtmlInputElement>();
-                if let Some(input) = input {
-                    //log::debug!("got input: {:?}", input.value());
-                    let message = WebSocketMessage {
-                        message_type: MsgTypes::Message,
-                        data: Some(input.value()),
-                        data_array: None,
-                    };
-                    if let Err(e) = self
-                        .wss
-                        .tx
-                        .clone()
-                        .try_send(serde_json::to_string(&message).unwrap())
-                    {
-                        log::debug!("error sending to channel: {:?}", e);
+                if matches!(self.composer_status(ctx), Some((_, true))) {
+                    return false;
+                }
+                // Enter on an empty composer should not produce blank messages.
+                match self.composer.submit() {
+                    Some(Submission::Edit { id, message }) => match codec::to_json(&MessageEdit { id: id.clone(), message: message.clone() }) {
+                        Ok(edit) => {
+                            self.send(&WebSocketMessage::new(MsgTypes::Edit, Some(edit)));
+                        }
+                        Err(e) => {
+                            // Keep the text and the edit so the user can retry.
+                            report_send_error(&e);
+                            self.composer.editing = Some(id);
+                            self.composer.text = message;
+                            return true;
+                        }
+                    },
+                    Some(Submission::Message { text, attachments }) => {
+                        self.attaching = false;
+                        let cx = CommandContext { me: &self.state.username };
+                        match ctx.props().commands.as_ref().and_then(|commands| commands.run(&text, &cx)) {
+                            Some(CommandOutput::Send(text)) => self.post(ctx, text, attachments),
+                            // Attachments wait for the next message.
+                            Some(output) => {
+                                self.composer.attachments = attachments;
+                                if let CommandOutput::Local(text) = output {
+                                    self.state.apply(WebSocketMessage::new(MsgTypes::Notice, Some(text)));
+                                }
+                            }
+                            None => self.post(ctx, text, attachments),
+                        }
+                    }
+                    None => {}
+                }
+                self.set_typing(false);
+                self.set_unload_guard(false);
+                true
+            }
+            Msg::CycleDisappearAfter => {
+                self.disappear_after = (self.disappear_after + 1) % DISAPPEAR_AFTER.len();
+                true
+            }
+            Msg::ExpiryTick => {
+                self.state.expire(js_sys::Date::now());
+                if self.state.next_expiry().is_none() {
+                    self.expiry_tick = None;
+                }
+                // The countdowns move on either way.
+                true
+            }
+            Msg::FocusComposer => {
+                if let Some(input) = self.chat_input.cast::<HtmlTextAreaElement>() {
+                    let _ = input.focus();
+                }
+                false
+            }
+            Msg::StartEdit(id) => {
+                let text = match self.state.message(&id) {
+                    Some(m) if m.from == self.state.username => m.message.clone(),
+                    _ => return false,
+                };
+                self.composer.text = text;
+                if let Some(input) = self.chat_input.cast::<HtmlTextAreaElement>() {
+                    let _ = input.focus();
+                }
+                self.set_unload_guard(true);
+                self.composer.editing = Some(id);
+                true
+            }
+            Msg::Quote(id) => {
+                let quoted = match self.state.message(&id) {
+                    Some(m) if !m.deleted => quote(&m.message),
+                    _ => return false,
+                };
+                // Replacing the value on render leaves the caret after the quote.
+                self.composer.append_quote(&quoted);
+                if let Some(input) = self.chat_input.cast::<HtmlTextAreaElement>() {
+                    let _ = input.focus();
+                }
+                self.set_unload_guard(true);
+                true
+            }
+            Msg::SendText(text) => {
+                if ctx.props().read_only || !self.in_room(ctx) || text.trim().is_empty() {
+                    log::warn!("message not sent: nothing to send or not in the room");
+                    return false;
+                }
+                if !ctx.props().queue_offline && self.connection != ConnectionState::Open {
+                    log::warn!("message not sent: disconnected and queue_offline is off");
+                    return false;
+                }
+                self.post(ctx, text, vec![]);
+                true
+            }
+            Msg::FilterRoster(filter) => {
+                self.roster_filter = filter;
+                true
+            }
+            Msg::ToggleRosterExpanded => {
+                self.roster_expanded = !self.roster_expanded;
+                true
+            }
+            Msg::CompleteMention(id) => {
+                let partial = match pending_mention(&self.composer.text) {
+                    Some(partial) => partial.len(),
+                    None => return false,
+                };
+                let typed = &self.composer.text[..self.composer.text.len() - partial];
+                let text = format!("{}{} ", typed, id);
+                self.set_composer_text(ctx, text);
+                if let Some(input) = self.chat_input.cast::<HtmlTextAreaElement>() {
+                    let _ = input.focus();
+                }
+                true
+            }
+            Msg::OpenThread(id) => {
+                if self.state.message(&id).is_none() {
+                    return false;
+                }
+                self.thread = Some(id);
+                if let Some(input) = self.thread_input.cast::<HtmlTextAreaElement>() {
+                    let _ = input.focus();
+                }
+                true
+            }
+            Msg::CloseThread => self.thread.take().is_some(),
+            Msg::ThreadInput(text) => {
+                self.thread_text = text;
+                // As with the main composer, keeps the rendered value current.
+                true
+            }
+            Msg::SubmitReply => {
+                let thread_id = match &self.thread {
+                    Some(root) if !self.thread_text.trim().is_empty() => root.clone(),
+                    _ => return false,
+                };
+                let reply = MessageReply { thread_id, message: self.thread_text.clone() };
+                let frame = match codec::to_json(&reply) {
+                    Ok(data) => WebSocketMessage::new(MsgTypes::Reply, Some(data)),
+                    Err(e) => {
+                        report_send_error(&e);
+                        return false;
+                    }
+                };
+                if self.send_tracked(ctx, frame) {
+                    self.thread_text.clear();
+                }
+                true
+            }
+            Msg::Format(style) => {
+                let input = match self.chat_input.cast::<HtmlTextAreaElement>() {
+                    Some(input) => input,
+                    None => return false,
+                };
+                let cursor = self.composer.text.encode_utf16().count() as u32;
+                let start = input.selection_start().ok().flatten().unwrap_or(cursor);
+                let end = input.selection_end().ok().flatten().unwrap_or(start);
+                let (value, start, end) = wrap_selection(&self.composer.text, start, end, style);
+                // Set right away so the selection can be restored now; the
+                // render that follows writes the same value and leaves it be.
+                input.set_value(&value);
+                let _ = input.focus();
+                let _ = input.set_selection_range(start, end);
+                self.set_composer_text(ctx, value);
+                true
+            }
+            Msg::ToggleEmojiPicker => {
+                self.emoji_picker = !self.emoji_picker;
+                if !self.emoji_picker {
+                    if let Some(input) = self.chat_input.cast::<HtmlTextAreaElement>() {
+                        let _ = input.focus();
+                    }
+                }
+                true
+            }
+            Msg::InsertEmoji(emoji) => {
+                self.emoji_picker = false;
+                let input = match self.chat_input.cast::<HtmlTextAreaElement>() {
+                    Some(input) => input,
+                    None => return true,
+                };
+                // The composer keeps its selection while the picker has focus.
+                let cursor = self.composer.text.encode_utf16().count() as u32;
+                let start = input.selection_start().ok().flatten().unwrap_or(cursor);
+                let end = input.selection_end().ok().flatten().unwrap_or(start);
+                let (value, cursor) = insert_at(&self.composer.text, start, end, emoji);
+                input.set_value(&value);
+                let _ = input.focus();
+                let _ = input.set_selection_range(cursor, cursor);
+                self.set_composer_text(ctx, value);
+                true
+            }
+            Msg::ToggleAttach => {
+                self.attaching = !self.attaching;
+                self.attachment_error = false;
+                true
+            }
+            Msg::AddAttachment => {
+                let input = match self.attachment_input.cast::<HtmlInputElement>() {
+                    Some(input) => input,
+                    None => return false,
+                };
+                let url = input.value().trim().to_string();
+                self.attachment_error = !is_http_url(&url);
+                if !self.attachment_error {
+                    if !self.composer.attachments.contains(&url) {
+                        self.composer.attachments.push(url);
                     }
                     input.set_value("");
+                }
+                let _ = input.focus();
+                true
+            }
+            Msg::RemoveAttachment(i) => {
+                if i < self.composer.attachments.len() {
+                    self.composer.attachments.remove(i);
+                }
+                true
+            }
+            Msg::DeleteMessage(id) => {
+                // The server only relays deletes from the author; this just avoids
+                // sending requests it would reject.
+                if !matches!(self.state.message(&id), Some(m) if m.from == self.state.username) {
+                    return false;
+                }
+                self.send(&WebSocketMessage::new(MsgTypes::Delete, Some(id)));
+                false
+            }
+            Msg::SubmitUsername => {
+                let value = self
+                    .username_input
+                    .cast::<HtmlInputElement>()
+                    .map(|input| input.value())
+                    .unwrap_or_default();
+                match validate_username(&value) {
+                    Ok(name) => {
+                        *self.user.username.borrow_mut() = name.clone();
+                        self.state.last_read = read_marker::load(&name);
+                        self.state.username = name;
+                        self.username_error = None;
+                        // Otherwise this happens as soon as the socket opens.
+                        if self.connection == ConnectionState::Open {
+                            self.register();
+                        }
+                    }
+                    Err(e) => self.username_error = Some(e),
+                }
+                true
+            }
+            Msg::ToggleMetrics => {
+                self.metrics_refresh = match self.metrics_refresh.take() {
+                    Some(_) => None,
+                    None => {
+                        let link = ctx.link().clone();
+                        Some(Interval::new(1_000, move || {
+                            link.send_message(Msg::RefreshMetrics)
+                        }))
+                    }
+                };
+                true
+            }
+            Msg::RefreshMetrics => self.metrics_refresh.is_some(),
+            Msg::ConnectionChanged(state) => {
+                match state {
+                    ConnectionState::Open => {
+                        self.reconnect_at = None;
+                        self.countdown = None;
+                        let link = ctx.link().clone();
+                        self.heartbeat = Some(Interval::new(PING_INTERVAL_MS, move || {
+                            link.send_message(Msg::Heartbeat)
+                        }));
+                        ctx.link().send_message(Msg::Heartbeat);
+                        // A request sent on the dropped socket will never be answered.
+                        self.loading_history = false;
+                        // Every new connection needs to register again.
+                        self.sent_receipt = None;
+                        if self.joins_roster(ctx) {
+                            self.register();
+                            // The server forgets our status with the connection.
+                            if self.idle {
+                                self.send_status(ctx);
+                            }
+                        }
+                        if self.in_room(ctx) && self.state.messages.is_empty() {
+                            self.request_history();
+                        }
+                    }
+                    ConnectionState::Reconnecting { delay_ms, .. } => {
+                        self.reconnect_at = Some(js_sys::Date::now() + delay_ms as f64);
+                        let link = ctx.link().clone();
+                        self.countdown = Some(Interval::new(1_000, move || {
+                            link.send_message(Msg::CountdownTick)
+                        }));
+                    }
+                    ConnectionState::GaveUp => {
+                        self.reconnect_at = None;
+                        self.countdown = None;
+                    }
+                    ConnectionState::Connecting | ConnectionState::Closed => {
+                        self.countdown = None;
+                    }
+                }
+                if state != ConnectionState::Open {
+                    self.heartbeat = None;
+                    self.latency.reset_pending();
+                }
+                self.connection = state;
+                true
+            }
+            Msg::ReconnectNow => {
+                self.wss.reconnect_now();
+                false
+            }
+            Msg::CountdownTick => true,
+            Msg::PresenceSettled => {
+                self.presence_timeout = None;
+                self.state.settle_presence()
+            }
+            Msg::Heartbeat => {
+                let id = self.latency.ping(js_sys::Date::now());
+                self.send(&WebSocketMessage::new(MsgTypes::Ping, Some(id.to_string())));
+                false
+            }
+            Msg::TogglePin(id) => {
+                let message_type = if self.state.is_pinned(&id) {
+                    MsgTypes::Unpin
+                } else {
+                    MsgTypes::Pin
+                };
+                self.send(&WebSocketMessage::new(message_type, Some(id)));
+                false
+            }
+            Msg::TogglePinnedBar => {
+                self.show_pinned = !self.show_pinned;
+                true
+            }
+            Msg::MovePin(id, index) => {
+                if !self.state.move_pin(&id, index) {
+                    return false;
+                }
+                self.send(&WebSocketMessage {
+                    data_array: Some(self.state.pin_order()),
+                    ..WebSocketMessage::new(MsgTypes::PinOrder, None)
+                });
+                true
+            }
+            Msg::DragPin(id) => {
+                self.dragged_pin = id;
+                true
+            }
+            Msg::DropPin(target) => {
+                let index = self.state.pinned.iter().position(|m| m.id.as_ref() == Some(&target));
+                if let (Some(id), Some(index)) = (self.dragged_pin.take(), index) {
+                    ctx.link().send_message(Msg::MovePin(id, index));
+                }
+                true
+            }
+            Msg::ShowMessage(id) => {
+                // Replies live in their thread's panel, which opens with the
+                // reply highlighted.
+                if let Some(root) = self.state.message(&id).and_then(|m| m.thread_id.clone()) {
+                    self.thread = Some(root);
+                } else {
+                    match gloo_utils::document().get_element_by_id(&message_anchor(&id)) {
+                        Some(el) => el.scroll_into_view(),
+                        None => {
+                            toast::notify(
+                                "That message is further back than the loaded history.",
+                                Severity::Info,
+                            );
+                            return false;
+                        }
+                    }
+                }
+                self.highlighted = Some(id);
+                let link = ctx.link().clone();
+                self.highlight_timeout = Some(Timeout::new(HIGHLIGHT_MS, move || {
+                    link.send_message(Msg::ClearHighlight)
+                }));
+                true
+            }
+            Msg::ToggleRaw(id) => {
+                if !self.raw_shown.remove(&id) {
+                    self.raw_shown.insert(id);
+                }
+                true
+            }
+            Msg::DownloadRaw(id) => {
+                let m = match self.state.message(&id) {
+                    Some(m) => m,
+                    None => return false,
                 };
+                let mime = m.content_type.as_deref().unwrap_or("application/octet-stream");
+                if let Err(e) = download::save(&format!("message-{}", id), mime, &m.message) {
+                    log::error!("failed to download the message: {:?}", e);
+                    toast::notify("Couldn't download the message.", Severity::Error);
+                }
+                false
+            }
+            Msg::ClearHighlight => {
+                self.highlight_timeout = None;
+                self.highlighted.take().is_some()
+            }
+            Msg::OpenImage(url) => {
+                self.lightbox = Some(url);
+                true
+            }
+            Msg::CloseImage => self.lightbox.take().is_some(),
+            Msg::DownloadImage(url) => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Err(e) = download::fetch(&url, &download_name(&url)).await {
+                        log::error!("failed to download {}: {:?}", url, e);
+                        toast::notify(
+                            "Couldn't download the image. Try opening the original instead.",
+                            Severity::Error,
+                        );
+                    }
+                });
+                false
+            }
+            Msg::CopyLink(id) => {
+                copy_permalink(&id);
+                false
+            }
+            Msg::ToggleSidebar => {
+                self.sidebar_open = !self.sidebar_open;
+                true
+            }
+            Msg::LayoutChanged => {
+                let narrow = is_narrow_screen();
+                if narrow == self.narrow {
+                    return false;
+                }
+                // The sheet starts closed, the sidebar open, as on load.
+                self.narrow = narrow;
+                self.sidebar_open = !narrow;
+                true
+            }
+            Msg::JumpToLatest => {
+                if !self.in_room(ctx) || gloo_utils::document().hidden() {
+                    return false;
+                }
+                self.state.mark_all_read();
+                self.scroll_restore = Some(ScrollRestore::Bottom);
+                true
+            }
+            Msg::MarkAllRead => self.state.mark_all_read(),
+            Msg::SendTimedOut => {
+                let now = js_sys::Date::now();
+                // Queued messages go out once the connection is back; only
+                // time spent connected counts against them.
+                if self.connection != ConnectionState::Open {
+                    self.outbox.postpone(now);
+                    self.schedule_resend(ctx);
+                    return false;
+                }
+                let expired = self.outbox.expire(now, SEND_CONFIRM_MS as f64);
+                for frame in &expired.resend {
+                    self.send(frame);
+                }
+                self.schedule_resend(ctx);
+                if expired.failed == 0 {
+                    return false;
+                }
+                toast::notify(
+                    "The server hasn't confirmed your last message. It may not have been delivered.",
+                    Severity::Warning,
+                );
+                true
+            }
+            Msg::CancelEdit => {
+                self.composer.text.clear();
+                self.set_unload_guard(false);
+                self.composer.editing = None;
+                true
+            }
+            Msg::InputChanged(text) => {
+                self.set_composer_text(ctx, text);
+                // Re-render even though the text box already shows the text, so
+                // the value yew last wrote stays current. Otherwise clearing
+                // the text after a send could look unchanged and be skipped.
+                true
+            }
+            Msg::Activity => {
+                let now = js_sys::Date::now();
+                if self.idle {
+                    self.idle = false;
+                    self.typing.set_away(false);
+                    self.send_status(ctx);
+                } else if now - self.last_activity < ACTIVITY_THROTTLE_MS {
+                    return false;
+                }
+                self.last_activity = now;
+                self.idle_timeout = ctx.props().idle_after_ms.map(|ms| {
+                    let link = ctx.link().clone();
+                    Timeout::new(ms, move || link.send_message(Msg::Idle))
+                });
+                false
+            }
+            Msg::Idle => {
+                self.idle_timeout = None;
+                self.idle = true;
+                if let Some(typing) = self.typing.set_away(true) {
+                    self.typing_timeout = None;
+                    self.send_typing(typing);
+                }
+                self.send_status(ctx);
+                false
+            }
+            Msg::TypingIdle => {
+                self.set_typing(false);
                 false
             }
+            Msg::ToggleSettings => {
+                self.show_settings = !self.show_settings;
+                true
+            }
+            Msg::ToggleExportMenu => {
+                self.show_export_menu = !self.show_export_menu;
+                true
+            }
+            Msg::Export(format) => {
+                self.show_export_menu = false;
+                self.export(format);
+                true
+            }
+            Msg::SettingsChanged(settings) => {
+                let avatar_changed = settings.avatar_url != self.settings.avatar_url;
+                if (settings.filter_words, &settings.filtered_words)
+                    != (self.settings.filter_words, &self.settings.filtered_words)
+                {
+                    self.word_filter = word_filter(&settings);
+                }
+                self.settings = settings;
+                if avatar_changed && self.joins_roster(ctx) {
+                    self.send_profile();
+                }
+                true
+            }
+            Msg::VisibilityChanged => {
+                self.state.away = gloo_utils::document().hidden();
+                // Background tabs barely run timers, so anything that ran out
+                // meanwhile goes as soon as we're back.
+                !self.state.away && self.state.expire(js_sys::Date::now())
+            }
+            Msg::MessagesScrolled => {
+                let el = match self.messages_container.cast::<Element>() {
+                    Some(el) => el,
+                    None => return false,
+                };
+                self.save_scroll_position(&el);
+                if el.scroll_top() <= 0 && self.request_history() {
+                    return true;
+                }
+                if self.state.first_unread().is_none() {
+                    return false;
+                }
+                if is_scrolled_to_bottom(&el) {
+                    return self.state.mark_all_read();
+                }
+                match self.last_visible_message(&el) {
+                    Some(i) => self.state.mark_read_through(i),
+                    None => false,
+                }
+            }
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        // The composer shows up on the first render, or right after the
+        // username form when the name still had to be picked.
+        if !self.autofocused && !ctx.props().read_only {
+            if let Some(input) = self.chat_input.cast::<HtmlTextAreaElement>() {
+                self.autofocused = true;
+                if !is_touch_device() {
+                    let _ = input.focus();
+                }
+            }
+        }
+        if let (Some(restore), Some(el)) = (
+            self.scroll_restore.take(),
+            self.messages_container.cast::<Element>(),
+        ) {
+            el.set_scroll_top(match restore {
+                ScrollRestore::Bottom => el.scroll_height(),
+                ScrollRestore::Top(offset) => offset,
+                ScrollRestore::FromBottom(offset) => el.scroll_height() - offset,
+            });
+        }
+        if self.state.last_read != self.saved_read {
+            if let Some(last_read) = self.state.last_read {
+                read_marker::save(&self.state.username, last_read);
+            }
+            self.saved_read = self.state.last_read;
+        }
+        self.send_receipt(ctx);
+        if self.expiry_tick.is_none() {
+            if let Some(expiry) = self.state.next_expiry() {
+                let link = ctx.link().clone();
+                self.expiry_tick = Some(Interval::new(1_000, move || link.send_message(Msg::ExpiryTick)));
+                // Already gone, e.g. when it came in with the history.
+                if expiry <= js_sys::Date::now() {
+                    ctx.link().send_message(Msg::ExpiryTick);
+                }
+            }
+        }
+        if let (Some(restore), Some(container)) = (
+            self.restore_position.as_ref(),
+            self.messages_container.cast::<Element>(),
+        ) {
+            if let Some(el) = gloo_utils::document().get_element_by_id(&message_anchor(&restore.message_id)) {
+                let top = el.get_bounding_client_rect().top() - container.get_bounding_client_rect().top();
+                container.set_scroll_top(container.scroll_top() + (top - restore.offset).round() as i32);
+                self.restore_position = None;
+            }
+        }
+        // After the scroll position is restored, or it would undo the jump.
+        if let Some(anchor) = &self.anchor {
+            if self.state.message(anchor).is_some() {
+                ctx.link().send_message(Msg::ShowMessage(anchor.clone()));
+                self.anchor = None;
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        if !self.in_room(ctx) {
+            return self.view_username_form(ctx);
+        }
         let submit = ctx.link().callback(|_| Msg::SubmitMessage);
+        let first_unread = self.state.first_unread();
+        let seen_by = self.state.seen_by();
+        let day_breaks = day_breaks(self.state.messages.iter().map(|m| m.time.map(local_day)));
+        let send_status = self.composer_status(ctx);
+        let blocked = matches!(send_status, Some((_, true)));
+        let oninput = ctx.link().callback(|e: InputEvent| {
+            Msg::InputChanged(e.target_unchecked_into::<HtmlTextAreaElement>().value())
+        });
+        let onkeydown = self.on_send_key(ctx, || Msg::SubmitMessage);
+        let limit = ctx.props().roster_limit.filter(|_| !self.roster_expanded);
+        let (roster, more) = self.state.roster_page(&self.roster_filter, limit);
+        let toggle_settings = ctx.link().callback(|_| Msg::ToggleSettings);
+        let onscroll = ctx.link().callback(|_| Msg::MessagesScrolled);
         html! {
-            <div class="flex h-screen w-screen font-sans">
-                // Sidebar
-                <div class="w-64 bg-white border-r border-gray-200 flex flex-col">
-                    <div class="text-2xl font-semibold text-gray-700 p-4 border-b">{"👥 Users"}</div>
-                    <div class="overflow-auto">
+            <div
+                class={classes!("yc-app", "flex", "w-screen", "font-sans", self.settings.high_contrast.then_some("yc-high-contrast"))}
+                style={self.theme(ctx).css_variables()}
+            >
+                // Sidebar, or on narrow screens a bottom sheet over the chat
+                {
+                    if self.narrow && self.sidebar_open {
+                        html! { <div onclick={ctx.link().callback(|_| Msg::ToggleSidebar)} class="fixed inset-0 z-30 bg-black bg-opacity-30"></div> }
+                    } else {
+                        html! {}
+                    }
+                }
+                <div class={classes!(
+                    "overflow-hidden", "bg-[color:var(--yc-surface)]", "border-gray-200", "duration-300",
+                    if self.narrow {
+                        classes!(
+                            "fixed", "inset-x-0", "bottom-0", "z-40", "h-[70vh]", "rounded-t-2xl", "shadow-2xl", "transition-transform",
+                            if self.sidebar_open { "translate-y-0" } else { "translate-y-full" },
+                        )
+                    } else {
+                        classes!(
+                            "flex-shrink-0", "transition-all",
+                            if self.sidebar_open { "w-64 border-r" } else { "w-0" },
+                        )
+                    },
+                )}>
+                    <div class={classes!("h-full", "flex", "flex-col", (!self.narrow).then_some("w-64"))}>
                         {
-                            self.users.iter().map(|u| {
+                            if self.narrow {
                                 html! {
-                                    <div class="flex items-center space-x-4 p-3 mx-2 my-2 rounded-lg hover:bg-gray-100 transition duration-200">
-                                        <img class="w-10 h-10 rounded-full border" src={u.avatar.clone()} />
-                                        <div>
-                                            <p class="text-sm font-medium text-gray-800">{u.name.clone()}</p>
-                                            <p class="text-xs text-gray-400">{"Hi there!"}</p>
-                                        </div>
-                                    </div>
+                                    <button
+                                        onclick={ctx.link().callback(|_| Msg::ToggleSidebar)}
+                                        aria-label="Hide users"
+                                        class="flex justify-center pt-2"
+                                    >
+                                        <span class="w-10 h-1 bg-gray-300 rounded-full"></span>
+                                    </button>
                                 }
-                            }).collect::<Html>()
+                            } else {
+                                html! {}
+                            }
                         }
+                        <div class="text-2xl font-semibold text-gray-700 p-4 border-b">{"👥 Users"}</div>
+                        <div class="px-3 pt-3">
+                            <input
+                                type="search"
+                                value={self.roster_filter.clone()}
+                                oninput={ctx.link().callback(|e: InputEvent| Msg::FilterRoster(e.target_unchecked_into::<HtmlInputElement>().value()))}
+                                placeholder="Find people"
+                                aria-label="Find people"
+                                class="w-full px-3 py-1 text-sm bg-[color:var(--yc-input)] rounded focus:outline-none focus:ring-2 focus:ring-[color:var(--yc-primary)]"
+                            />
+                        </div>
+                        <div class="flex-1 min-h-0 overflow-auto">
+                            {
+                                if self.show_skeleton(ctx) {
+                                    view_roster_skeleton()
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            {
+                                if roster.is_empty() && !self.roster_filter.is_empty() {
+                                    html! { <p class="p-4 text-sm text-gray-400">{"No one matches."}</p> }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            {
+                                roster.into_iter().map(|(u, presence)| {
+                                    let transition = match presence {
+                                        Presence::Present => None,
+                                        Presence::Joined => Some("motion-safe:animate-presence-in"),
+                                        Presence::Leaving => Some("motion-safe:animate-presence-out pointer-events-none"),
+                                    };
+                                    html! {
+                                        <div key={u.id.clone()} class={classes!(
+                                            "flex", "items-center", "space-x-4", "p-3", "mx-2", "my-2", "rounded-lg",
+                                            "hover:bg-gray-100", "transition", "duration-200", transition,
+                                        )}>
+                                            <div class="relative flex-shrink-0">
+                                                <Avatar class="w-10 h-10 border" src={u.avatar.clone()} name={u.name.clone()} />
+                                                <span
+                                                    title={if u.away { "Away" } else { "Active" }}
+                                                    class={classes!(
+                                                        "absolute", self.settings.avatar_shape.status_dot_class(), "w-3", "h-3", "rounded-full", "border-2", "border-white",
+                                                        if u.away { "bg-gray-300" } else { "bg-green-500" },
+                                                    )}
+                                                ></span>
+                                            </div>
+                                            // Long names are cut off with an ellipsis; hovering shows the full name.
+                                            <div class="min-w-0">
+                                                <p class="text-sm font-medium text-gray-800 truncate" title={u.name.clone()}>{u.name.clone()}</p>
+                                                <p class="text-xs text-gray-400">{if u.away { "Away" } else { "Hi there!" }}</p>
+                                            </div>
+                                        </div>
+                                    }
+                                }).collect::<Html>()
+                            }
+                            {
+                                if more > 0 {
+                                    html! {
+                                        <button onclick={ctx.link().callback(|_| Msg::ToggleRosterExpanded)} class="w-full px-5 py-2 text-sm text-left text-gray-500 hover:text-gray-800">
+                                            {format!("and {} more…", more)}
+                                        </button>
+                                    }
+                                } else if self.roster_expanded && ctx.props().roster_limit.is_some_and(|limit| self.state.users.len() > limit) {
+                                    html! {
+                                        <button onclick={ctx.link().callback(|_| Msg::ToggleRosterExpanded)} class="w-full px-5 py-2 text-sm text-left text-gray-500 hover:text-gray-800">
+                                            {"Show fewer"}
+                                        </button>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                        </div>
                     </div>
                 </div>
 
                 // Chat Area
-                <div class="flex flex-col flex-1">
-                    <div class="h-14 flex items-center px-6 border-b text-xl font-semibold bg-gray-50">{"💬 Chat Room"}</div>
-                    <div class="flex-1 overflow-y-auto px-6 py-4 space-y-4 bg-gray-50">
+                <div class="flex flex-col flex-1 min-w-0">
+                    <div class="h-14 flex items-center justify-between px-6 border-b bg-[color:var(--yc-background)]">
+                        <div class="flex items-center space-x-3">
+                            <button
+                                onclick={ctx.link().callback(|_| Msg::ToggleSidebar)}
+                                title={if self.sidebar_open { "Hide users" } else { "Show users" }}
+                                class="text-xl text-gray-500 hover:text-gray-800"
+                            >
+                                {"☰"}
+                            </button>
+                            <span class="text-xl font-semibold">{format!("💬 {}", ROOM_NAME)}</span>
+                            {self.view_room_badge()}
+                            {self.view_latency()}
+                        </div>
+                        <div class="flex items-center space-x-3">
+                            {self.view_export_menu(ctx)}
+                            <button
+                                onclick={toggle_settings}
+                                title="Settings"
+                                class="text-xl text-gray-500 hover:text-gray-800"
+                            >
+                                {"⚙️"}
+                            </button>
+                        </div>
+                    </div>
+                    {self.view_connection_banner(ctx)}
+                    {self.view_pinned_bar(ctx)}
+                    <div ref={self.messages_container.clone()} {onscroll} class="flex-1 overflow-y-auto px-6 py-4 space-y-4 bg-[color:var(--yc-background)]">
+                        {
+                            if self.show_skeleton(ctx) {
+                                view_messages_skeleton()
+                            } else if self.initial_data
+                                && self.state.messages.is_empty()
+                                && self.connection == ConnectionState::Open
+                            {
+                                self.view_empty_state(ctx)
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {
+                            if self.loading_history {
+                                html! {
+                                    <div class="flex justify-center py-2">
+                                        <div class="w-5 h-5 border-2 border-gray-300 border-t-blue-500 rounded-full animate-spin"></div>
+                                    </div>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                         {
-                            self.messages.iter().map(|m| {
-                                let user = self.users.iter().find(|u| u.name == m.from);
-                                if let Some(user) = user {
+                            self.state.messages.iter().enumerate().map(|(i, m)| {
+                                let divider = if first_unread == Some(i) {
                                     html! {
-                                        <div class="flex items-start space-x-3">
-                                            <img class="w-8 h-8 rounded-full border" src={user.avatar.clone()} />
-                                            <div>
-                                                <p class="text-sm font-medium text-gray-800">{m.from.clone()}</p>
-                                                {
-                                                    if m.message.ends_with(".gif") {
-                                                        html! { <img src={m.message.clone()} class="mt-2 max-w-xs rounded-lg shadow-sm"/> }
-                                                    } else {
-                                                        html! { <p class="mt-1 text-sm bg-white p-3 rounded-lg shadow-sm text-gray-800">{m.message.clone()}</p> }
-                                                    }
-                                                }
-                                            </div>
+                                        <div class="flex items-center space-x-3 text-xs font-semibold text-red-500 uppercase">
+                                            <div class="flex-1 border-t border-red-300"></div>
+                                            <span>{format!("New messages ({})", self.state.unread_count())}</span>
+                                            <button onclick={ctx.link().callback(|_| Msg::MarkAllRead)} class="normal-case hover:underline">
+                                                {"Mark all as read"}
+                                            </button>
+                                            <div class="flex-1 border-t border-red-300"></div>
                                         </div>
                                     }
                                 } else {
                                     html! {}
+                                };
+                                let seen = m.id.as_deref().and_then(|id| seen_by.get(id));
+                                html! {
+                                    <>
+                                        {day_breaks[i].map_or_else(|| html! {}, view_day_separator)}
+                                        {divider}
+                                        {self.view_message(ctx, m)}
+                                        {seen.map_or_else(|| html! {}, |users| view_seen_by(users))}
+                                    </>
                                 }
                             }).collect::<Html>()
                         }
                     </div>
 
+                    {self.view_typing_indicator()}
+
+                    {
+                        if self.composer.editing.is_some() {
+                            html! {
+                                <div class="flex items-center justify-between px-6 py-1 text-xs text-blue-700 bg-blue-50 border-t">
+                                    <span>{"Editing message"}</span>
+                                    <button onclick={ctx.link().callback(|_| Msg::CancelEdit)} class="hover:underline">{"Cancel"}</button>
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+
+                    {self.view_format_toolbar(ctx)}
+                    {self.view_attachments(ctx)}
+                    {self.view_quick_replies(ctx)}
+                    {self.view_mention_suggestions(ctx)}
+
                     // Chat Input
-                    <div class="h-16 flex items-center px-4 bg-white border-t">
-                        <div class="flex items-center w-full space-x-3">
-                            <input
-                                ref={self.chat_input.clone()}
-                                type="text"
-                                placeholder="Type a message..."
-                                class="flex-grow py-2 px-4 bg-gray-100 rounded-full text-sm focus:outline-none focus:ring-2 focus:ring-blue-400"
-                            />
-                            <button
+                    <div class={classes!(
+                        "h-16", "items-center", "px-4", "bg-[color:var(--yc-surface)]",
+                        if ctx.props().read_only { "hidden" } else { "flex" },
+                    )}>
+                        <div class="relative flex items-center w-full space-x-3">
+                            {
+                                if self.emoji_picker {
+                                    html! {
+                                        <EmojiPicker
+                                            onpick={ctx.link().callback(Msg::InsertEmoji)}
+                                            onclose={ctx.link().callback(|_| Msg::ToggleEmojiPicker)}
+                                        />
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            <button
+                                type="button"
+                                onclick={ctx.link().callback(|_| Msg::ToggleEmojiPicker)}
+                                title="Emoji"
+                                aria-label="Emoji"
+                                aria-expanded={self.emoji_picker.to_string()}
+                                class={classes!(
+                                    "flex-shrink-0", "text-xl", "hover:opacity-100",
+                                    if self.emoji_picker { "opacity-100" } else { "opacity-60" },
+                                )}
+                            >
+                                {"😊"}
+                            </button>
+                            <button
+                                type="button"
+                                onclick={ctx.link().callback(|_| Msg::ToggleAttach)}
+                                title="Attach a link"
+                                aria-label="Attach a link"
+                                class={classes!(
+                                    "flex-shrink-0", "text-xl", "hover:text-gray-800",
+                                    if self.attaching { "text-gray-800" } else { "text-gray-500" },
+                                )}
+                            >
+                                {"📎"}
+                            </button>
+                            {self.view_disappear_button(ctx)}
+                            <textarea
+                                ref={self.chat_input.clone()}
+                                // Controlled: yew only writes the value when it differs
+                                // from the last render, and an unchanged value keeps the caret.
+                                value={self.composer.text.clone()}
+                                {oninput}
+                                {onkeydown}
+                                rows="1"
+                                placeholder={send_status.map_or("Type a message...", |(status, _)| status)}
+                                class="flex-grow py-2 px-4 bg-[color:var(--yc-input)] rounded-full text-sm resize-none focus:outline-none focus:ring-2 focus:ring-[color:var(--yc-primary)]"
+                            />
+                            <button
                                 onclick={submit}
-                                class="flex items-center justify-center w-10 h-10 bg-blue-600 hover:bg-blue-700 text-white rounded-full transition duration-200 shadow"
+                                disabled={blocked}
+                                title={send_status.map_or("Send", |(status, _)| status)}
+                                class="flex items-center justify-center w-10 h-10 bg-[color:var(--yc-primary)] hover:bg-[color:var(--yc-primary-hover)] disabled:opacity-50 disabled:cursor-not-allowed text-white rounded-full transition duration-200 shadow"
                             >
-                                <svg class="w-5 h-5" fill="none" stroke="currentColor" stroke-width="2" viewBox="0 0 24 24">
-                                    <path stroke-linecap="round" stroke-linejoin="round" d="M5 13l4 4L19 7" />
-                                </svg>
+                                {
+                                    if !self.outbox.is_empty() {
+                                        html! { <div class="w-5 h-5 border-2 border-blue-200 border-t-white rounded-full animate-spin"></div> }
+                                    } else {
+                                        html! {
+                                            <svg class="w-5 h-5" fill="none" stroke="currentColor" stroke-width="2" viewBox="0 0 24 24">
+                                                <path stroke-linecap="round" stroke-linejoin="round" d="M5 13l4 4L19 7" />
+                                            </svg>
+                                        }
+                                    }
+                                }
                             </button>
                         </div>
                     </div>
                 </div>
+
+                {self.view_thread_panel(ctx)}
+
+                {frame_log_overlay()}
+
+                {self.view_lightbox(ctx)}
+
+                {
+                    if self.metrics_refresh.is_some() {
+                        let m = metrics::snapshot();
+                        html! {
+                            <div class="fixed bottom-20 right-4 z-40 p-3 text-xs font-mono text-green-300 bg-gray-900 bg-opacity-90 rounded shadow">
+                                <p>{format!("connection: {:?}", m.connection)}</p>
+                                <p>{format!("sent: {}", m.messages_sent)}</p>
+                                <p>{format!("received: {}", m.messages_received)}</p>
+                                <p>{format!("errors: {}", m.errors)}</p>
+                                <p>{format!("reconnects: {}", m.reconnects)}</p>
+                                <p>{format!("rtt: {}", m.rtt_ms.map_or_else(|| "-".to_string(), |ms| format!("{:.0} ms", ms)))}</p>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+
+                {
+                    if self.show_settings {
+                        html! { <SettingsPanel onclose={ctx.link().callback(|_| Msg::ToggleSettings)} /> }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+}
+
+// The avatars of people who have read up to a message, right-aligned under it.
+fn view_seen_by(users: &[&UserProfile]) -> Html {
+    let names = users.iter().map(|u| u.name.as_str()).collect::<Vec<_>>().join(", ");
+    let overflow = users.len().saturating_sub(SEEN_BY_AVATARS);
+    html! {
+        <div title={format!("Seen by {}", names)} class="flex justify-end items-center -space-x-1">
+            {
+                users.iter().take(SEEN_BY_AVATARS).map(|u| html! {
+                    <Avatar
+                        src={u.avatar.clone()}
+                        name={u.name.clone()}
+                        class={classes!("w-4", "h-4", "ring-2", "ring-white")}
+                    />
+                }).collect::<Html>()
+            }
+            {
+                if overflow > 0 {
+                    html! { <span class="pl-2 text-xs text-gray-400">{format!("+{}", overflow)}</span> }
+                } else {
+                    html! {}
+                }
+            }
+        </div>
+    }
+}
+
+fn word_filter(settings: &Settings) -> Option<WordFilter> {
+    let filter = WordFilter::new(&settings.filtered_words);
+    (settings.filter_words && !filter.is_empty()).then_some(filter)
+}
+
+fn reply_count(count: usize) -> String {
+    if count == 1 {
+        "1 reply".into()
+    } else {
+        format!("{} replies", count)
+    }
+}
+
+// A piece of rendered message text. Plain text stays a string until the
+// whitespace at the edges has been trimmed.
+enum Part {
+    Text(String),
+    Html(Html),
+}
+
+// Text and links first, then every image in the message as a thumbnail gallery.
+fn view_message_body(
+    message: &str,
+    attachments: &[String],
+    renderers: &Renderers,
+    cx: &RenderContext,
+    size: ImageSize,
+    on_image: &Callback<String>,
+    on_download: &Callback<String>,
+) -> Html {
+    let mut images = vec![];
+    let mut paragraphs = vec![];
+    for chunk in code_blocks(message) {
+        let text = match chunk {
+            // Code is shown verbatim, without the segment renderers.
+            Chunk::Code { language, code } => {
+                paragraphs.push(html! { <CodeBlock code={code.to_string()} language={language.map(String::from)} /> });
+                continue;
+            }
+            Chunk::Text(text) => text,
+        };
+        for block in blocks(text) {
+            let mut inline = vec![];
+            for segment in tokenize(&block.text) {
+                match renderers.render(&segment, cx) {
+                    Some(Rendered::Inline(html)) => inline.push(Part::Html(html)),
+                    Some(Rendered::Image(url)) => images.push(url),
+                    None => inline.push(Part::Text(plain_text(&segment).into_owned())),
+                }
+            }
+            // Drop the whitespace that separated the text from removed images
+            // and from neighbouring blocks.
+            if let Some(Part::Text(t)) = inline.first_mut() {
+                *t = t.trim_start().to_string();
+            }
+            if let Some(Part::Text(t)) = inline.last_mut() {
+                *t = t.trim_end().to_string();
+            }
+            inline.retain(|part| !matches!(part, Part::Text(t) if t.is_empty()));
+            if inline.is_empty() {
+                continue;
+            }
+            let inline = inline.into_iter().map(|part| match part {
+                Part::Text(t) => html! { {t} },
+                Part::Html(html) => html,
+            }).collect::<Html>();
+            paragraphs.push(if block.quoted {
+                html! { <blockquote class="pl-3 my-1 border-l-4 border-gray-300 text-gray-500">{inline}</blockquote> }
+            } else {
+                html! { <p>{inline}</p> }
+            });
+        }
+    }
+    let (image_attachments, files): (Vec<&String>, Vec<&String>) =
+        attachments.iter().partition(|url| is_image_url(url));
+    images.extend(image_attachments.into_iter().cloned());
+    let text = if paragraphs.is_empty() {
+        html! {}
+    } else {
+        html! {
+            <div class="mt-1 text-sm bg-[color:var(--yc-bubble)] p-3 rounded-lg shadow-sm border-l-2 border-[color:var(--yc-author)] text-[color:var(--yc-bubble-text)] whitespace-pre-wrap">
+                {paragraphs.into_iter().collect::<Html>()}
+            </div>
+        }
+    };
+    let files = if files.is_empty() {
+        html! {}
+    } else {
+        html! {
+            <div class="flex flex-wrap gap-2 mt-2">
+                {
+                    files.into_iter().map(|url| html! {
+                        <a
+                            href={url.clone()}
+                            target="_blank"
+                            rel="noopener noreferrer"
+                            title={url.clone()}
+                            class="flex items-center max-w-xs px-3 py-1 space-x-1 text-sm text-blue-600 bg-[color:var(--yc-bubble)] border rounded-lg shadow-sm hover:underline"
+                        >
+                            <span>{"📎"}</span>
+                            <span class="truncate">{file_name(url).to_string()}</span>
+                        </a>
+                    }).collect::<Html>()
+                }
+            </div>
+        }
+    };
+    html! { <>{text}{view_gallery(&images, size, on_image, on_download)}{files}</> }
+}
+
+fn view_gallery(
+    images: &[String],
+    size: ImageSize,
+    on_image: &Callback<String>,
+    on_download: &Callback<String>,
+) -> Html {
+    // Widths for a lone image and for a gallery, and a gallery cell's height.
+    let (single, grid, cell) = match size {
+        ImageSize::Small => ("max-w-[10rem]", "max-w-xs", "h-20"),
+        ImageSize::Medium => ("max-w-xs", "max-w-md", "h-32"),
+        ImageSize::Large => ("max-w-lg", "max-w-2xl", "h-48"),
+    };
+    let thumbnail = |url: &str, class: Classes| {
+        let open = on_image.reform({
+            let url = url.to_string();
+            move |_| url.clone()
+        });
+        let download = on_download.reform({
+            let url = url.to_string();
+            move |_| url.clone()
+        });
+        html! {
+            // Named group, so hovering the message row doesn't reveal every button.
+            <div class="relative group/image">
+                <button onclick={open} class="block cursor-zoom-in">
+                    <img src={url.to_string()} {class} />
+                </button>
+                <button
+                    onclick={download}
+                    title="Download"
+                    aria-label="Download image"
+                    class="absolute top-1 right-1 px-1.5 py-0.5 text-xs text-white bg-black bg-opacity-60 rounded opacity-0 group-hover/image:opacity-100 focus:opacity-100 hover:bg-opacity-80"
+                >
+                    {"⬇"}
+                </button>
+            </div>
+        }
+    };
+    match images {
+        [] => html! {},
+        [url] => html! {
+            <div class="mt-2">{thumbnail(url, classes!(single, "rounded-lg", "shadow-sm"))}</div>
+        },
+        urls => html! {
+            <div class={classes!("mt-2", "grid", "grid-cols-2", "sm:grid-cols-3", "gap-2", grid)}>
+                {
+                    urls.iter()
+                        .map(|url| thumbnail(url, classes!("w-full", cell, "object-cover", "rounded-lg", "shadow-sm")))
+                        .collect::<Html>()
+                }
+            </div>
+        },
+    }
+}
+
+fn dispatch_message_event(message: &MessageData) {
+    let detail = serde_json::to_string(message)
+        .ok()
+        .and_then(|json| js_sys::JSON::parse(&json).ok());
+    let mut init = CustomEventInit::new();
+    init.detail(&detail.unwrap_or(JsValue::NULL));
+    match CustomEvent::new_with_event_init_dict("chat:message", &init) {
+        Ok(event) => {
+            let _ = gloo_utils::window().dispatch_event(&event);
+        }
+        Err(e) => log::error!("failed to create the chat:message event: {:?}", e),
+    }
+}
+
+// The date heading above the first message of a day.
+fn view_day_separator(day: (u32, u32, u32)) -> Html {
+    let now = js_sys::Date::now();
+    let label = if day == local_day(now) {
+        "Today".to_string()
+    } else if day == local_day(now - 86_400_000.0) {
+        "Yesterday".to_string()
+    } else {
+        format_date(day.0, day.1, day.2)
+    };
+    html! {
+        <div class="flex items-center space-x-3 text-xs font-medium text-gray-400">
+            <div class="flex-1 border-t border-gray-200"></div>
+            <span>{label}</span>
+            <div class="flex-1 border-t border-gray-200"></div>
+        </div>
+    }
+}
+
+// Shimmering user rows shown while the roster loads.
+fn view_roster_skeleton() -> Html {
+    html! {
+        <div class="motion-safe:animate-pulse" aria-busy="true" aria-label="Loading users">
+            {
+                ["w-24", "w-32", "w-20"].iter().map(|width| html! {
+                    <div class="flex items-center space-x-4 p-3 mx-2 my-2">
+                        <div class="flex-shrink-0 w-10 h-10 rounded-full bg-gray-200"></div>
+                        <div class="space-y-2">
+                            <div class={classes!("h-3", "rounded", "bg-gray-200", *width)}></div>
+                            <div class="w-16 h-2 rounded bg-gray-100"></div>
+                        </div>
+                    </div>
+                }).collect::<Html>()
+            }
+        </div>
+    }
+}
+
+// Shimmering message bubbles shown while the first history page loads.
+fn view_messages_skeleton() -> Html {
+    html! {
+        <div class="space-y-4 motion-safe:animate-pulse" aria-busy="true" aria-label="Loading messages">
+            {
+                ["w-48", "w-72", "w-40", "w-64"].iter().map(|width| html! {
+                    <div class="flex items-start space-x-3">
+                        <div class="flex-shrink-0 w-8 h-8 rounded-full bg-gray-200"></div>
+                        <div class="space-y-2">
+                            <div class="w-20 h-2 rounded bg-gray-200"></div>
+                            <div class={classes!("h-10", "rounded-lg", "bg-gray-200", *width)}></div>
+                        </div>
+                    </div>
+                }).collect::<Html>()
+            }
+        </div>
+    }
+}
+
+// A full or closed queue is already shown by the connection state; a frame
+// that can't be encoded is a bug the user should hear about.
+fn report_send_error(error: &WsError) {
+    match error {
+        WsError::Serialize(_) => {
+            log::error!("{}", error);
+            toast::notify("Couldn't send that message. Please try again.", Severity::Error);
+        }
+        WsError::Queue(_) => debug_log!("{}", error),
+    }
+}
+
+// Copies the URL linking to message `id` and confirms with a toast.
+fn copy_permalink(id: &str) {
+    let location = gloo_utils::window().location();
+    let (Ok(origin), Ok(path)) = (location.origin(), location.pathname()) else {
+        return;
+    };
+    let url = format!("{}{}#{}", origin, path, message_anchor(id));
+    wasm_bindgen_futures::spawn_local(async move {
+        match clipboard::write_text(&url).await {
+            Ok(()) => toast::notify("Link copied.", Severity::Success),
+            Err(_) => toast::notify("Couldn't copy the link.", Severity::Error),
+        }
+    });
+}
+
+// Below Tailwind's `md` breakpoint, where the user list becomes a sheet.
+const NARROW_SCREEN: &str = "(max-width: 767px)";
+
+fn is_narrow_screen() -> bool {
+    gloo_utils::window()
+        .match_media(NARROW_SCREEN)
+        .ok()
+        .flatten()
+        .map(|query| query.matches())
+        .unwrap_or(false)
+}
+
+fn is_editing_text(e: &KeyboardEvent) -> bool {
+    e.target()
+        .and_then(|target| target.dyn_into::<Element>().ok())
+        .map(|el| matches!(el.tag_name().as_str(), "INPUT" | "TEXTAREA"))
+        .unwrap_or(false)
+}
+
+fn is_scrolled_to_bottom(el: &Element) -> bool {
+    el.scroll_top() + el.client_height() >= el.scroll_height() - 1
+}
+
+// Focusing the composer on a phone pops the on-screen keyboard up over the
+// messages before the user has read anything.
+fn is_touch_device() -> bool {
+    gloo_utils::window()
+        .match_media("(pointer: coarse)")
+        .ok()
+        .flatten()
+        .map(|query| query.matches())
+        .unwrap_or(false)
+}
+
+#[cfg(feature = "debug-overlay")]
+fn frame_log_overlay() -> Html {
+    html! { <crate::components::frame_log::FrameLog /> }
+}
+
+#[cfg(not(feature = "debug-overlay"))]
+fn frame_log_overlay() -> Html {
+    html! {}
+}
+
+impl Chat {
+    // The user's high-contrast preference wins over the embedding app's theme.
+    fn theme(&self, ctx: &Context<Self>) -> Theme {
+        if self.settings.high_contrast {
+            Theme::high_contrast()
+        } else {
+            ctx.props().theme.clone()
+        }
+    }
+
+    // Read-only clients see the room even without a username.
+    fn in_room(&self, ctx: &Context<Self>) -> bool {
+        ctx.props().read_only || !self.state.username.is_empty()
+    }
+
+    // Why sending is delayed or unavailable while the connection isn't
+    // open, and whether it is blocked outright.
+    fn composer_status(&self, ctx: &Context<Self>) -> Option<(&'static str, bool)> {
+        let reconnecting = match self.connection {
+            ConnectionState::Open => return None,
+            ConnectionState::Connecting | ConnectionState::Reconnecting { .. } => true,
+            ConnectionState::GaveUp | ConnectionState::Closed => false,
+        };
+        Some(match (ctx.props().queue_offline, reconnecting) {
+            // Sent messages only queue up; one is enough to show the send
+            // registered.
+            (true, _) if !self.outbox.is_empty() => ("Waiting for the connection…", true),
+            (true, true) => ("Reconnecting — messages will be queued", false),
+            (true, false) => ("Disconnected — messages will be sent when you reconnect", false),
+            (false, true) => ("Reconnecting", true),
+            (false, false) => ("Disconnected", true),
+        })
+    }
+
+    // Placeholders stand in until the first data arrives, unless we've
+    // stopped trying to connect and the banner explains the empty room.
+    fn show_skeleton(&self, ctx: &Context<Self>) -> bool {
+        self.in_room(ctx)
+            && !self.initial_data
+            && !matches!(self.connection, ConnectionState::GaveUp | ConnectionState::Closed)
+    }
+
+    fn joins_roster(&self, ctx: &Context<Self>) -> bool {
+        let props = ctx.props();
+        !self.state.username.is_empty() && (!props.read_only || props.register_spectator)
+    }
+
+    fn register(&self) {
+        self.send(&WebSocketMessage::new(MsgTypes::Register, Some(self.state.username.clone())));
+        if self.settings.avatar_url.is_some() {
+            self.send_profile();
+        }
+    }
+
+    // Asks for the page of messages before the oldest one loaded. Returns
+    // false if a request is already pending or there is nothing older.
+    fn request_history(&mut self) -> bool {
+        if self.loading_history || self.state.history_complete {
+            return false;
+        }
+        self.loading_history = true;
+        let before = self.state.messages.first().and_then(|m| m.id.clone());
+        self.send(&WebSocketMessage::new(MsgTypes::History, before));
+        true
+    }
+
+    fn send_profile(&self) {
+        let avatar = self.settings.avatar_url.clone().unwrap_or_default();
+        self.send(&WebSocketMessage::new(MsgTypes::Profile, Some(avatar)));
+    }
+
+    // The room switcher's unread badge; the server only has the one room.
+    // Unread messages come back after a reload through the read marker.
+    fn view_room_badge(&self) -> Html {
+        match self.state.unread_count() {
+            0 => html! {},
+            count => html! {
+                <span
+                    title={format!("{} unread", count)}
+                    class="px-2 py-0.5 text-xs font-semibold text-white bg-[color:var(--yc-primary)] rounded-full"
+                >
+                    {count}
+                </span>
+            },
+        }
+    }
+
+    fn view_pinned_bar(&self, ctx: &Context<Self>) -> Html {
+        if self.state.pinned.is_empty() {
+            return html! {};
+        }
+        let count = self.state.pinned.len();
+        html! {
+            <div class="px-6 py-2 text-sm bg-white border-b">
+                <button onclick={ctx.link().callback(|_| Msg::TogglePinnedBar)} class="font-semibold text-gray-600 hover:text-gray-900">
+                    {format!("📌 {} pinned {} {}", count, if count == 1 { "message" } else { "messages" }, if self.show_pinned { "▴" } else { "▾" })}
+                </button>
+                {
+                    if self.show_pinned {
+                        html! {
+                            <ul class="mt-2 space-y-1">
+                                {
+                                    // Newest pins are listed first, so "up" is further along `pinned`.
+                                    self.state.pinned.iter().enumerate().rev().filter_map(|(i, m)| {
+                                        let id = m.id.clone()?;
+                                        Some(self.view_pin(ctx, m, id, i))
+                                    }).collect::<Html>()
+                                }
+                            </ul>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    // A row of the pinned list: draggable onto another row to take its place,
+    // with up and down buttons doing the same from the keyboard.
+    fn view_pin(&self, ctx: &Context<Self>, m: &MessageData, id: String, index: usize) -> Html {
+        let last = self.state.pinned.len() - 1;
+        let ondragstart = {
+            let id = id.clone();
+            ctx.link().callback(move |e: DragEvent| {
+                // Firefox only starts a drag that carries data.
+                if let Some(transfer) = e.data_transfer() {
+                    let _ = transfer.set_data("text/plain", &id);
+                }
+                Msg::DragPin(Some(id.clone()))
+            })
+        };
+        let target = id.clone();
+        let ondrop = ctx.link().callback(move |e: DragEvent| {
+            e.prevent_default();
+            Msg::DropPin(target.clone())
+        });
+        let move_button = |label: &'static str, title: &'static str, to: Option<usize>| {
+            let id = id.clone();
+            html! {
+                <button
+                    onclick={ctx.link().callback(move |_| Msg::MovePin(id.clone(), to.unwrap_or(index)))}
+                    disabled={to.is_none()}
+                    {title}
+                    aria-label={title}
+                    class="px-1 text-gray-400 hover:text-gray-700 disabled:invisible"
+                >
+                    {label}
+                </button>
+            }
+        };
+        let show_id = id.clone();
+        html! {
+            <li
+                draggable="true"
+                {ondragstart}
+                ondragend={ctx.link().callback(|_| Msg::DragPin(None))}
+                // Allows dropping here.
+                ondragover={Callback::from(|e: DragEvent| e.prevent_default())}
+                {ondrop}
+                class={classes!(
+                    "flex", "items-center", "rounded", "cursor-move",
+                    (self.dragged_pin.as_ref() == Some(&id)).then_some("opacity-50"),
+                )}
+            >
+                <button
+                    onclick={ctx.link().callback(move |_| Msg::ShowMessage(show_id.clone()))}
+                    class="flex-1 min-w-0 text-left truncate text-gray-700 hover:text-blue-600"
+                >
+                    <span class="font-medium">{self.state.display_name(&m.from).to_string()}</span>
+                    {": "}
+                    {m.message.clone()}
+                </button>
+                {move_button("↑", "Move up", (index < last).then_some(index + 1))}
+                {move_button("↓", "Move down", index.checked_sub(1))}
+            </li>
+        }
+    }
+
+    // The oldest message still partly in view, and how far its top sits below
+    // the top of the list (negative once it has scrolled past).
+    fn first_visible_message(&self, container: &Element) -> Option<ScrollPosition> {
+        let top = container.get_bounding_client_rect().top();
+        let document = gloo_utils::document();
+        self.state.messages.iter().find_map(|m| {
+            let id = m.id.as_deref()?;
+            let rect = document.get_element_by_id(&message_anchor(id))?.get_bounding_client_rect();
+            (rect.bottom() > top).then(|| ScrollPosition { message_id: id.to_string(), offset: rect.top() - top })
+        })
+    }
+
+    // Remembers where the list is scrolled to for the next visit; at the
+    // bottom there is nothing to remember, as that's where the room opens.
+    fn save_scroll_position(&mut self, container: &Element) {
+        // Until the old position is back, scrolling is ours, not the user's.
+        if self.restore_position.is_some() {
+            return;
+        }
+        let position = if is_scrolled_to_bottom(container) {
+            None
+        } else {
+            self.first_visible_message(container)
+        };
+        if position == self.saved_position {
+            return;
+        }
+        match &position {
+            Some(position) => scroll_position::save(ROOM_ID, position),
+            None => scroll_position::clear(ROOM_ID),
+        }
+        self.saved_position = position;
+    }
+
+    // Index of the newest message whose top has scrolled into view.
+    fn last_visible_message(&self, container: &Element) -> Option<usize> {
+        let bottom = container.get_bounding_client_rect().bottom();
+        let document = gloo_utils::document();
+        self.state.messages.iter().enumerate().rev().find_map(|(i, m)| {
+            let el = document.get_element_by_id(&message_anchor(m.id.as_deref()?))?;
+            (el.get_bounding_client_rect().top() < bottom).then_some(i)
+        })
+    }
+
+    fn view_latency(&self) -> Html {
+        match self.latency.average() {
+            Some(rtt) if self.settings.show_latency && self.connection == ConnectionState::Open => html! {
+                <span title="Round-trip time to the server" class="text-xs font-mono text-gray-400">
+                    {format!("{:.0}ms", rtt)}
+                </span>
+            },
+            _ => html! {},
+        }
+    }
+
+    fn view_lightbox(&self, ctx: &Context<Self>) -> Html {
+        let url = match &self.lightbox {
+            Some(url) => url.clone(),
+            None => return html! {},
+        };
+        let download = ctx.link().callback({
+            let url = url.clone();
+            move |e: MouseEvent| {
+                e.stop_propagation();
+                Msg::DownloadImage(url.clone())
+            }
+        });
+        html! {
+            <div
+                onclick={ctx.link().callback(|_| Msg::CloseImage)}
+                class="fixed inset-0 z-50 flex flex-col items-center justify-center p-8 space-y-3 bg-black bg-opacity-80 cursor-zoom-out"
+            >
+                <img src={url.clone()} class="max-w-full max-h-full object-contain rounded shadow-lg" />
+                <div class="flex space-x-4">
+                    <button
+                        onclick={download}
+                        class="text-sm text-gray-300 hover:text-white hover:underline"
+                    >
+                        {"Download"}
+                    </button>
+                    <a
+                        href={url}
+                        target="_blank"
+                        rel="noopener noreferrer"
+                        onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                        class="text-sm text-gray-300 hover:text-white hover:underline"
+                    >
+                        {"Open original"}
+                    </a>
+                </div>
+            </div>
+        }
+    }
+
+    fn view_export_menu(&self, ctx: &Context<Self>) -> Html {
+        let menu = if self.show_export_menu {
+            html! {
+                <div class="absolute right-0 z-30 mt-2 w-40 py-1 bg-white border rounded shadow-lg">
+                    {
+                        [ExportFormat::Json, ExportFormat::Text].iter().map(|&format| html! {
+                            <button
+                                onclick={ctx.link().callback(move |_| Msg::Export(format))}
+                                class="block w-full px-3 py-1 text-sm text-left text-gray-700 hover:bg-gray-100"
+                            >
+                                {format.label()}
+                            </button>
+                        }).collect::<Html>()
+                    }
+                </div>
+            }
+        } else {
+            html! {}
+        };
+        html! {
+            <div class="relative">
+                <button
+                    onclick={ctx.link().callback(|_| Msg::ToggleExportMenu)}
+                    title="Export history"
+                    class="text-xl text-gray-500 hover:text-gray-800"
+                >
+                    {"📥"}
+                </button>
+                {menu}
+            </div>
+        }
+    }
+
+    // Downloads the loaded history. Older pages that were never scrolled to
+    // aren't included.
+    fn export(&self, format: ExportFormat) {
+        let messages = &self.state.messages;
+        let contents = match format {
+            ExportFormat::Json => match export::to_json(ROOM_NAME, messages, js_sys::Date::now()) {
+                Ok(json) => json,
+                Err(e) => {
+                    log::error!("failed to export history: {}", e);
+                    toast::notify("Couldn't export the history.", Severity::Error);
+                    return;
+                }
+            },
+            ExportFormat::Text => {
+                let time_format = self.settings.time_format;
+                export::to_transcript(ROOM_NAME, messages, |time| format_date_time(time, time_format))
+            }
+        };
+        let file_name = format!("yewchat-history.{}", format.extension());
+        if let Err(e) = download::save(&file_name, format.mime_type(), &contents) {
+            log::error!("failed to download the export: {:?}", e);
+            toast::notify("Couldn't export the history.", Severity::Error);
+        }
+    }
+
+    // Sends `submit` when the key press is the configured send key.
+    fn on_send_key(&self, ctx: &Context<Self>, submit: fn() -> Msg) -> Callback<KeyboardEvent> {
+        let send_key = self.settings.send_key;
+        ctx.link().batch_callback(move |e: KeyboardEvent| {
+            if e.key() != "Enter" {
+                return None;
+            }
+            let sends = match send_key {
+                SendKey::Enter => !e.shift_key(),
+                SendKey::CtrlEnter => e.ctrl_key() || e.meta_key(),
+            };
+            if !sends {
+                return None;
+            }
+            e.prevent_default();
+            Some(submit())
+        })
+    }
+
+    // Sends a new chat message, whether typed or from `Msg::SendText`.
+    fn post(&mut self, ctx: &Context<Self>, text: String, attachments: Vec<String>) {
+        let message = WebSocketMessage {
+            data_array: Some(attachments).filter(|urls| !urls.is_empty()),
+            expires_at: DISAPPEAR_AFTER[self.disappear_after].0.map(|ms| js_sys::Date::now() + ms as f64),
+            ..WebSocketMessage::new(MsgTypes::Message, Some(text))
+        };
+        self.send_tracked(ctx, message);
+    }
+
+    // Sends a message that the server echoes back, resending it if the echo
+    // doesn't come in time. Returns whether it went into the queue.
+    fn send_tracked(&mut self, ctx: &Context<Self>, frame: WebSocketMessage) -> bool {
+        let frame = self.outbox.track(frame, js_sys::Date::now());
+        if !self.send(&frame) {
+            self.outbox.confirm(frame.client_id.as_deref().unwrap_or_default());
+            return false;
+        }
+        self.schedule_resend(ctx);
+        true
+    }
+
+    fn schedule_resend(&mut self, ctx: &Context<Self>) {
+        let due_in = self
+            .outbox
+            .next_deadline(SEND_CONFIRM_MS as f64)
+            .map(|deadline| (deadline - js_sys::Date::now()).max(0.0) as u32);
+        self.send_timeout = due_in.map(|ms| {
+            let link = ctx.link().clone();
+            Timeout::new(ms, move || link.send_message(Msg::SendTimedOut))
+        });
+    }
+
+    // Records new composer text and tells others whether we're typing.
+    fn set_composer_text(&mut self, ctx: &Context<Self>, text: String) {
+        let has_text = !text.is_empty();
+        self.composer.text = text;
+        if has_text {
+            self.set_typing(true);
+            let link = ctx.link().clone();
+            self.typing_timeout = Some(Timeout::new(TYPING_IDLE_MS, move || {
+                link.send_message(Msg::TypingIdle)
+            }));
+        } else {
+            self.set_typing(false);
+        }
+        self.set_unload_guard(has_text);
+    }
+
+    // The best-matching people for a mention being typed at the end of the
+    // composer.
+    fn view_mention_suggestions(&self, ctx: &Context<Self>) -> Html {
+        let partial = match pending_mention(&self.composer.text) {
+            Some(partial) if !ctx.props().read_only => partial,
+            _ => return html! {},
+        };
+        let others = self.state.users.iter().filter(|u| u.id != self.state.username);
+        let matches = fuzzy::rank(others, |u| fuzzy::best_score(partial, &[&u.id, &u.name]));
+        if matches.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div role="listbox" aria-label="Mention suggestions" class="flex px-4 pt-2 space-x-2 overflow-x-auto bg-[color:var(--yc-surface)]">
+                {
+                    matches.into_iter().take(MENTION_SUGGESTIONS).map(|u| {
+                        let id = u.id.clone();
+                        html! {
+                            <button
+                                type="button"
+                                role="option"
+                                // Keeps focus in the composer.
+                                onmousedown={Callback::from(|e: MouseEvent| e.prevent_default())}
+                                onclick={ctx.link().callback(move |_| Msg::CompleteMention(id.clone()))}
+                                class="flex flex-shrink-0 items-center px-2 py-1 space-x-2 text-sm text-gray-700 bg-[color:var(--yc-input)] rounded-full hover:bg-gray-200"
+                            >
+                                <Avatar class="w-5 h-5" src={u.avatar.clone()} name={u.name.clone()} />
+                                <span>{u.name.clone()}</span>
+                                {
+                                    if u.name != u.id {
+                                        html! { <span class="text-xs text-gray-400">{format!("@{}", u.id)}</span> }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                            </button>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        }
+    }
+
+    // Hidden once the user starts typing, or while editing.
+    fn view_quick_replies(&self, ctx: &Context<Self>) -> Html {
+        let replies = &ctx.props().quick_replies;
+        if ctx.props().read_only || replies.is_empty() || !self.composer.text.is_empty() || self.composer.editing.is_some() {
+            return html! {};
+        }
+        html! {
+            <div class="flex px-4 pt-2 space-x-2 overflow-x-auto bg-[color:var(--yc-surface)]">
+                {
+                    replies.iter().map(|reply| {
+                        let text = reply.clone();
+                        html! {
+                            <button
+                                type="button"
+                                onclick={ctx.link().callback(move |_| Msg::SendText(text.clone()))}
+                                class="flex-shrink-0 px-3 py-1 text-sm text-gray-700 bg-[color:var(--yc-input)] rounded-full hover:bg-gray-200"
+                            >
+                                {reply.clone()}
+                            </button>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        }
+    }
+
+    fn view_format_toolbar(&self, ctx: &Context<Self>) -> Html {
+        if ctx.props().read_only {
+            return html! {};
+        }
+        let button = |style: Style, title: &'static str, label: Html| {
+            html! {
+                <button
+                    type="button"
+                    {title}
+                    aria-label={title}
+                    // Keeps focus, and so the selection, in the composer.
+                    onmousedown={Callback::from(|e: MouseEvent| e.prevent_default())}
+                    onclick={ctx.link().callback(move |_| Msg::Format(style))}
+                    class="w-7 h-7 text-sm text-gray-600 rounded hover:bg-gray-100 hover:text-gray-800"
+                >
+                    {label}
+                </button>
+            }
+        };
+        html! {
+            <div class="flex items-center px-4 pt-2 space-x-1 bg-[color:var(--yc-surface)] border-t">
+                {button(Style::Bold, "Bold", html! { <span class="font-bold">{"B"}</span> })}
+                {button(Style::Italic, "Italic", html! { <span class="italic font-serif">{"I"}</span> })}
+                {button(Style::Code, "Code", html! { <span class="font-mono">{"</>"}</span> })}
+            </div>
+        }
+    }
+
+    // The attachments waiting to be sent, and the field for adding one.
+    fn view_attachments(&self, ctx: &Context<Self>) -> Html {
+        if ctx.props().read_only || (self.composer.attachments.is_empty() && !self.attaching) {
+            return html! {};
+        }
+        let field = if self.attaching {
+            let onkeydown = ctx.link().batch_callback(|e: KeyboardEvent| {
+                (e.key() == "Enter").then(|| {
+                    e.prevent_default();
+                    Msg::AddAttachment
+                })
+            });
+            html! {
+                <div class="space-y-1">
+                    <div class="flex space-x-2">
+                        <input
+                            ref={self.attachment_input.clone()}
+                            {onkeydown}
+                            placeholder="https://example.com/screenshot.png"
+                            class="flex-grow px-2 py-1 text-sm border rounded"
+                        />
+                        <button
+                            onclick={ctx.link().callback(|_| Msg::AddAttachment)}
+                            class="px-3 py-1 text-sm text-white bg-[color:var(--yc-primary)] hover:bg-[color:var(--yc-primary-hover)] rounded"
+                        >
+                            {"Attach"}
+                        </button>
+                    </div>
+                    {
+                        if self.attachment_error {
+                            html! { <p class="text-xs text-red-500">{"Enter an http(s) link"}</p> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </div>
+            }
+        } else {
+            html! {}
+        };
+        html! {
+            <div class="px-4 pt-2 space-y-2 bg-[color:var(--yc-surface)]">
+                {field}
+                <div class="flex flex-wrap gap-2">
+                    {
+                        self.composer.attachments.iter().enumerate().map(|(i, url)| html! {
+                            <span class="flex items-center max-w-xs px-2 py-1 space-x-1 text-xs text-gray-700 bg-gray-100 rounded" title={url.clone()}>
+                                <span>{if is_image_url(url) { "🖼️" } else { "📎" }}</span>
+                                <span class="truncate">{file_name(url).to_string()}</span>
+                                <button
+                                    onclick={ctx.link().callback(move |_| Msg::RemoveAttachment(i))}
+                                    aria-label="Remove attachment"
+                                    class="text-gray-400 hover:text-gray-700"
+                                >
+                                    {"✕"}
+                                </button>
+                            </span>
+                        }).collect::<Html>()
+                    }
+                </div>
+            </div>
+        }
+    }
+
+    fn view_typing_indicator(&self) -> Html {
+        let names = self
+            .state
+            .typing_users
+            .iter()
+            .map(|id| self.state.display_name(id))
+            .collect::<Vec<_>>();
+        match typing_summary(&names) {
+            Some(summary) => html! {
+                <div class="px-6 py-1 text-xs italic text-gray-500 bg-[color:var(--yc-background)]">{summary}</div>
+            },
+            None => html! {},
+        }
+    }
+
+    fn view_connection_banner(&self, ctx: &Context<Self>) -> Html {
+        if self.connection == ConnectionState::GaveUp {
+            return html! {
+                <div class="flex items-center justify-between px-6 py-2 text-sm text-red-800 bg-red-100 border-b border-red-200">
+                    <span>{"Connection lost — reload to retry."}</span>
+                    <button onclick={ctx.link().callback(|_| Msg::ReconnectNow)} class="font-semibold hover:underline">
+                        {"Try again"}
+                    </button>
+                </div>
+            };
+        }
+        let text = match (self.connection, self.reconnect_at) {
+            (ConnectionState::Reconnecting { .. }, Some(at)) => {
+                let seconds = ((at - js_sys::Date::now()) / 1000.0).ceil().max(0.0);
+                format!("Connection lost. Reconnecting in {}s…", seconds)
+            }
+            (ConnectionState::Connecting, Some(_)) => "Reconnecting…".to_string(),
+            _ => return html! {},
+        };
+        html! {
+            <div class="flex items-center justify-between px-6 py-2 text-sm text-yellow-800 bg-yellow-100 border-b border-yellow-200">
+                <span>{text}</span>
+                {
+                    if matches!(self.connection, ConnectionState::Reconnecting { .. }) {
+                        html! {
+                            <button onclick={ctx.link().callback(|_| Msg::ReconnectNow)} class="font-semibold hover:underline">
+                                {"Reconnect now"}
+                            </button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    fn view_username_form(&self, ctx: &Context<Self>) -> Html {
+        let onsubmit = ctx.link().callback(|e: FocusEvent| {
+            e.prevent_default();
+            Msg::SubmitUsername
+        });
+        html! {
+            <div class="flex h-screen w-screen items-center justify-center bg-gray-800 font-sans">
+                <form {onsubmit} class="flex flex-col items-center space-y-2">
+                    <div class="flex">
+                        <input
+                            ref={self.username_input.clone()}
+                            class="rounded-l-lg p-4 border-t mr-0 border-b border-l text-gray-800 border-gray-200 bg-white"
+                            placeholder="Pick a username to join"
+                        />
+                        <button type="submit" class="px-8 rounded-r-lg bg-violet-600 text-white font-bold p-4 uppercase border-violet-600 border-t border-b border-r">
+                            {"Join"}
+                        </button>
+                    </div>
+                    {
+                        if let Some(error) = self.username_error {
+                            html! { <p class="text-sm text-red-400">{error}</p> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </form>
+            </div>
+        }
+    }
+
+    fn view_message(&self, ctx: &Context<Self>, m: &MessageData) -> Html {
+        if m.category == Category::System {
+            return html! {
+                <p class="text-center text-xs text-gray-400">{m.message.clone()}</p>
+            };
+        }
+        if m.category == Category::Announcement {
+            return html! {
+                <div role="status" class="flex items-start px-4 py-2 space-x-2 text-sm text-amber-900 bg-amber-50 border border-amber-200 rounded-lg">
+                    <span aria-hidden="true">{"📢"}</span>
+                    <p class="whitespace-pre-wrap">{m.message.clone()}</p>
+                </div>
+            };
+        }
+        // Authors who have left the room are no longer in the roster.
+        let departed_profile;
+        let (user, departed) = match self.state.user(&m.from) {
+            Some(user) => (user, false),
+            None if self.settings.hide_departed => return html! {},
+            None => {
+                departed_profile = UserProfile::new(
+                    RosterEntry {
+                        id: m.from.clone(),
+                        display_name: m.display_name.clone().unwrap_or_else(|| m.from.clone()),
+                        avatar: None,
+                        away: false,
+                    },
+                    self.state.avatar_api,
+                );
+                (&departed_profile, true)
+            }
+        };
+        let actions = match &m.id {
+            Some(id) if !m.deleted && !ctx.props().read_only => {
+                let pin_id = id.clone();
+                let link_id = id.clone();
+                let quote_id = id.clone();
+                let reply = if m.thread_id.is_none() {
+                    let thread_id = id.clone();
+                    html! {
+                        <button onclick={ctx.link().callback(move |_| Msg::OpenThread(thread_id.clone()))} class="hover:text-gray-700">{"Reply"}</button>
+                    }
+                } else {
+                    html! {}
+                };
+                let pin_label = if self.state.is_pinned(id) { "Unpin" } else { "Pin" };
+                let own = if m.from == self.state.username {
+                    let edit_id = id.clone();
+                    let delete_id = id.clone();
+                    html! {
+                        <>
+                            <button onclick={ctx.link().callback(move |_| Msg::StartEdit(edit_id.clone()))} class="hover:text-gray-700">{"Edit"}</button>
+                            <button onclick={ctx.link().callback(move |_| Msg::DeleteMessage(delete_id.clone()))} class="hover:text-red-600">{"Delete"}</button>
+                        </>
+                    }
+                } else {
+                    html! {}
+                };
+                html! {
+                    <div class="hidden group-hover:flex flex-shrink-0 space-x-2 text-xs text-gray-400">
+                        {reply}
+                        <button onclick={ctx.link().callback(move |_| Msg::TogglePin(pin_id.clone()))} class="hover:text-gray-700">{pin_label}</button>
+                        <button onclick={ctx.link().callback(move |_| Msg::Quote(quote_id.clone()))} class="hover:text-gray-700">{"Quote"}</button>
+                        <button onclick={ctx.link().callback(move |_| Msg::CopyLink(link_id.clone()))} class="hover:text-gray-700">{"Copy link"}</button>
+                        {own}
+                    </div>
+                }
+            }
+            _ => html! {},
+        };
+        let name = m.display_name.clone().unwrap_or_else(|| user.name.clone());
+        let highlighted = m.id.is_some() && m.id == self.highlighted;
+        // Our own messages don't need pointing out to us.
+        let mentions_me = !self.state.username.is_empty()
+            && m.from != self.state.username
+            && (mentions(&m.message, &self.state.username) || mentions_everyone(&m.message));
+        html! {
+            // Bubbles take their left border from `--yc-author`.
+            <div id={m.id.as_deref().map(message_anchor)} style={format!("--yc-author: {}", username_color(&m.from))} class={classes!(
+                "group", "flex", "items-start", "space-x-3", "transition-colors", "duration-500",
+                highlighted.then_some("bg-blue-50 -mx-2 px-2 py-1 rounded-lg"),
+                departed.then_some("opacity-50"),
+                mentions_me.then_some("bg-yellow-50 -mx-2 px-2 py-1 rounded-lg"),
+            )}>
+                <Avatar class="flex-shrink-0 w-8 h-8 border" src={user.avatar.clone()} name={user.name.clone()} />
+                <div class="min-w-0">
+                    <div class="flex items-center space-x-2">
+                        <p class="flex items-baseline min-w-0 text-sm font-medium text-gray-800">
+                            <span class="truncate" title={name.clone()}>{name}</span>
+                            {
+                                match (m.time, self.settings.timestamps) {
+                                    (Some(time), TimestampDisplay::Always | TimestampDisplay::Hover) => html! {
+                                        <span
+                                            title={format_date_time(time, self.settings.time_format)}
+                                            class={classes!(
+                                                "flex-shrink-0", "ml-2", "text-xs", "font-normal", "text-gray-400",
+                                                // Kept in the layout so the row doesn't shift on hover.
+                                                (self.settings.timestamps == TimestampDisplay::Hover).then_some(
+                                                    "opacity-0 group-hover:opacity-100 group-focus-within:opacity-100 transition-opacity"
+                                                ),
+                                            )}
+                                        >
+                                            {format_time(time, self.settings.time_format)}
+                                        </span>
+                                    },
+                                    _ => html! {},
+                                }
+                            }
+                            {
+                                if m.duplicates.is_empty() {
+                                    html! {}
+                                } else {
+                                    let count = m.duplicates.len() + 1;
+                                    html! {
+                                        <span title={format!("Sent {} times", count)} class="flex-shrink-0 ml-1 px-1 text-xs font-semibold text-gray-500 bg-gray-200 rounded">
+                                            {format!("×{}", count)}
+                                        </span>
+                                    }
+                                }
+                            }
+                            {
+                                match m.expires_at {
+                                    Some(at) => html! {
+                                        <span title="Disappears when the time runs out" class="flex-shrink-0 ml-1 px-1 text-xs font-normal text-amber-700 bg-amber-100 rounded">
+                                            {format!("⏱ {}", format_countdown(at - js_sys::Date::now()))}
+                                        </span>
+                                    },
+                                    None => html! {},
+                                }
+                            }
+                            {
+                                if m.edited {
+                                    html! { <span class="flex-shrink-0 ml-1 text-xs font-normal text-gray-400">{"(edited)"}</span> }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                        </p>
+                        {actions}
+                    </div>
+                    {
+                        if m.deleted {
+                            html! { <p class="mt-1 text-sm italic text-gray-400">{"Message deleted"}</p> }
+                        } else {
+                            self.view_content(ctx, m)
+                        }
+                    }
+                    {self.view_reply_count(ctx, m)}
+                </div>
+            </div>
+        }
+    }
+
+    // A message's body, shown according to its content type.
+    fn view_content(&self, ctx: &Context<Self>, m: &MessageData) -> Html {
+        let cx = RenderContext {
+            me: &self.state.username,
+            // An empty roster almost always just hasn't loaded yet.
+            roster: (!self.state.users.is_empty()).then_some(self.state.users.as_slice()),
+        };
+        let on_image = ctx.link().callback(Msg::OpenImage);
+        let on_download = ctx.link().callback(Msg::DownloadImage);
+        let text = match &self.word_filter {
+            Some(filter) => filter.mask(&m.message),
+            None => Cow::Borrowed(m.message.as_str()),
+        };
+        match self.renderers.content(m.content_type.as_deref()) {
+            Content::Markdown => view_message_body(
+                &text,
+                &m.attachments,
+                &self.renderers,
+                &cx,
+                self.settings.image_size,
+                &on_image,
+                &on_download,
+            ),
+            Content::Plain => html! {
+                <div class="mt-1 text-sm bg-[color:var(--yc-bubble)] p-3 rounded-lg shadow-sm border-l-2 border-[color:var(--yc-author)] text-[color:var(--yc-bubble-text)] whitespace-pre-wrap">
+                    {text.into_owned()}
+                </div>
+            },
+            Content::Image if is_http_url(&m.message) => {
+                view_gallery(std::slice::from_ref(&m.message), self.settings.image_size, &on_image, &on_download)
+            }
+            Content::Custom(renderer) => html! { <div class="mt-1">{renderer.render(&m.message, &cx)}</div> },
+            Content::Image | Content::Unsupported => self.view_unsupported(ctx, m),
+        }
+    }
+
+    // What stands in for content we can't show: the type, and the raw body
+    // to read or save.
+    fn view_unsupported(&self, ctx: &Context<Self>, m: &MessageData) -> Html {
+        let content_type = m.content_type.clone().unwrap_or_default();
+        let id = m.id.clone().unwrap_or_default();
+        let raw_shown = self.raw_shown.contains(&id);
+        let toggle = {
+            let id = id.clone();
+            ctx.link().callback(move |_| Msg::ToggleRaw(id.clone()))
+        };
+        let download = ctx.link().callback(move |_| Msg::DownloadRaw(id.clone()));
+        html! {
+            <div class="mt-1 p-3 space-y-2 text-sm bg-[color:var(--yc-bubble)] border border-dashed rounded-lg">
+                <p class="text-gray-500">{format!("Unsupported content ({})", content_type)}</p>
+                <div class="flex space-x-3 text-xs">
+                    <button onclick={toggle} class="text-blue-600 hover:underline">
+                        {if raw_shown { "Hide raw" } else { "Show raw" }}
+                    </button>
+                    <button onclick={download} class="text-blue-600 hover:underline">{"Download"}</button>
+                </div>
+                {
+                    if raw_shown {
+                        html! { <pre class="max-h-64 overflow-auto p-2 text-xs font-mono bg-gray-100 rounded whitespace-pre-wrap break-all">{m.message.clone()}</pre> }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    // Sets how long the messages we send stay up; lit while they disappear.
+    fn view_disappear_button(&self, ctx: &Context<Self>) -> Html {
+        let (duration, short, long) = DISAPPEAR_AFTER[self.disappear_after];
+        html! {
+            <button
+                type="button"
+                onclick={ctx.link().callback(|_| Msg::CycleDisappearAfter)}
+                title={format!("Disappearing messages: {}", long)}
+                aria-label={format!("Disappearing messages: {}", long)}
+                class={classes!(
+                    "flex-shrink-0", "flex", "items-center", "text-xl", "hover:text-gray-800",
+                    if duration.is_some() { "text-amber-600" } else { "text-gray-500" },
+                )}
+            >
+                {"⏱"}
+                <span class="text-xs font-semibold">{short}</span>
+            </button>
+        }
+    }
+
+    fn view_empty_state(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let action = match &props.empty_action {
+            Some(label) if !props.read_only => html! {
+                <button
+                    onclick={ctx.link().callback(|_| Msg::FocusComposer)}
+                    class="px-4 py-1.5 text-sm font-medium text-white rounded-full bg-[color:var(--yc-primary)] hover:opacity-90"
+                >
+                    {label.clone()}
+                </button>
+            },
+            _ => html! {},
+        };
+        html! {
+            <div class="flex flex-col items-center py-8 space-y-3">
+                <p class="text-sm text-center text-gray-400">{props.empty_message.clone()}</p>
+                {action}
+            </div>
+        }
+    }
+
+    // "N replies" under a message with a thread, opening it.
+    fn view_reply_count(&self, ctx: &Context<Self>, m: &MessageData) -> Html {
+        let id = match &m.id {
+            Some(id) if m.thread_id.is_none() => id.clone(),
+            _ => return html! {},
+        };
+        let count = self.state.replies(&id).len();
+        if count == 0 {
+            return html! {};
+        }
+        html! {
+            <button
+                onclick={ctx.link().callback(move |_| Msg::OpenThread(id.clone()))}
+                class="mt-1 text-xs font-medium text-[color:var(--yc-primary)] hover:underline"
+            >
+                {reply_count(count)}
+            </button>
+        }
+    }
+
+    fn view_thread_panel(&self, ctx: &Context<Self>) -> Html {
+        let root = match self.thread.as_deref().and_then(|id| self.state.message(id)) {
+            Some(root) => root,
+            None => return html! {},
+        };
+        let replies = self.state.replies(root.id.as_deref().unwrap_or_default());
+        let oninput = ctx.link().callback(|e: InputEvent| {
+            Msg::ThreadInput(e.target_unchecked_into::<HtmlTextAreaElement>().value())
+        });
+        html! {
+            <div class="flex flex-col flex-shrink-0 w-80 border-l bg-[color:var(--yc-background)]">
+                <div class="h-14 flex items-center justify-between px-4 border-b">
+                    <span class="font-semibold">{"Thread"}</span>
+                    <button
+                        onclick={ctx.link().callback(|_| Msg::CloseThread)}
+                        title="Close thread"
+                        aria-label="Close thread"
+                        class="text-xl text-gray-500 hover:text-gray-800"
+                    >
+                        {"✕"}
+                    </button>
+                </div>
+                <div class="flex-1 overflow-y-auto px-4 py-4 space-y-4">
+                    {self.view_message(ctx, root)}
+                    <div class="flex items-center space-x-3 text-xs text-gray-400">
+                        <span>{reply_count(replies.len())}</span>
+                        <div class="flex-1 border-t"></div>
+                    </div>
+                    {replies.iter().map(|m| self.view_message(ctx, m)).collect::<Html>()}
+                </div>
+                {
+                    if ctx.props().read_only {
+                        html! {}
+                    } else {
+                        html! {
+                            <div class="flex items-center p-3 space-x-2 bg-[color:var(--yc-surface)]">
+                                <textarea
+                                    ref={self.thread_input.clone()}
+                                    value={self.thread_text.clone()}
+                                    {oninput}
+                                    onkeydown={self.on_send_key(ctx, || Msg::SubmitReply)}
+                                    rows="1"
+                                    placeholder="Reply…"
+                                    class="flex-grow py-2 px-4 bg-[color:var(--yc-input)] rounded-full text-sm resize-none focus:outline-none focus:ring-2 focus:ring-[color:var(--yc-primary)]"
+                                />
+                                <button
+                                    onclick={ctx.link().callback(|_| Msg::SubmitReply)}
+                                    title="Reply"
+                                    class="px-3 py-2 text-sm text-white bg-[color:var(--yc-primary)] hover:bg-[color:var(--yc-primary-hover)] rounded-full"
+                                >
+                                    {"Send"}
+                                </button>
+                            </div>
+                        }
+                    }
+                }
             </div>
         }
     }
-}
\ No newline at end of file
+
+    // Returns whether the frame made it into the outgoing queue.
+    fn send(&self, message: &WebSocketMessage) -> bool {
+        let queued = self.codec.encode_payload(message).and_then(|payload| {
+            self.wss
+                .tx
+                .clone()
+                .try_send(payload)
+                .map_err(|e| WsError::Queue(e.to_string()))
+        });
+        match queued {
+            Ok(()) => true,
+            Err(e) => {
+                report_send_error(&e);
+                false
+            }
+        }
+    }
+
+    fn set_unload_guard(&mut self, active: bool) {
+        if !active {
+            self.unload_guard = None;
+        } else if self.unload_guard.is_none() {
+            self.unload_guard = Some(EventListener::new(
+                &gloo_utils::window(),
+                "beforeunload",
+                |e| {
+                    e.prevent_default();
+                    if let Some(e) = e.dyn_ref::<BeforeUnloadEvent>() {
+                        e.set_return_value("You have an unsent message.");
+                    }
+                },
+            ));
+        }
+    }
+
+    // Tells the room how far we have read, whenever that moves on.
+    fn send_receipt(&mut self, ctx: &Context<Self>) {
+        if !self.joins_roster(ctx) || self.connection != ConnectionState::Open {
+            return;
+        }
+        let newest = self.state.newest_read();
+        if newest.is_none() || newest == self.sent_receipt.as_deref() {
+            return;
+        }
+        let newest = newest.map(String::from);
+        self.send(&WebSocketMessage::new(MsgTypes::Read, newest.clone()));
+        self.sent_receipt = newest;
+    }
+
+    // Tells the others whether we're away, for the roster.
+    fn send_status(&self, ctx: &Context<Self>) {
+        if !self.joins_roster(ctx) {
+            return;
+        }
+        let status = if self.idle { "away" } else { "active" };
+        self.send(&WebSocketMessage::new(MsgTypes::Status, Some(status.into())));
+    }
+
+    // Only transitions are sent, so a burst of keypresses produces a single frame.
+    fn set_typing(&mut self, active: bool) {
+        if !active {
+            self.typing_timeout = None;
+        }
+        if let Some(active) = self.typing.set_typing(active) {
+            self.send_typing(active);
+        }
+    }
+
+    fn send_typing(&self, active: bool) {
+        self.send(&WebSocketMessage::new(MsgTypes::Typing, Some(active.to_string())));
+    }
+}
@@ -0,0 +1,79 @@
+use gloo_timers::callback::Timeout;
+use wasm_bindgen_futures::spawn_local;
+use yew::functional::*;
+use yew::prelude::*;
+
+use crate::services::clipboard;
+use crate::services::toast::{self, Severity};
+
+// How long the button says "Copied" after a copy.
+const COPIED_MS: u32 = 1_500;
+
+#[derive(Properties, PartialEq)]
+pub struct CodeBlockProps {
+    pub code: String,
+    #[prop_or_default]
+    pub language: Option<String>,
+}
+
+/// A fenced code block from a message, with a copy button on hover that
+/// copies just the code.
+#[function_component(CodeBlock)]
+pub fn code_block(props: &CodeBlockProps) -> Html {
+    let copied = use_state(|| false);
+    let reset = use_mut_ref(|| None::<Timeout>);
+
+    let onclick = {
+        let code = props.code.clone();
+        let copied = copied.clone();
+        Callback::from(move |_| {
+            let (code, copied, reset) = (code.clone(), copied.clone(), reset.clone());
+            spawn_local(async move {
+                match clipboard::write_text(&code).await {
+                    Ok(()) => {
+                        copied.set(true);
+                        let copied = copied.clone();
+                        *reset.borrow_mut() =
+                            Some(Timeout::new(COPIED_MS, move || copied.set(false)));
+                    }
+                    Err(e) => {
+                        log::error!("failed to copy the code: {:?}", e);
+                        toast::notify("Couldn't copy the code.", Severity::Error);
+                    }
+                }
+            });
+        })
+    };
+
+    // Named group, so hovering the message row doesn't reveal the button.
+    html! {
+        <div class="relative my-1 group/code">
+            {
+                match &props.language {
+                    Some(language) => html! {
+                        <div class="px-3 pt-1 text-xs text-gray-400 bg-gray-800 rounded-t">{language.clone()}</div>
+                    },
+                    None => html! {},
+                }
+            }
+            <pre class={classes!(
+                "p-3", "overflow-x-auto", "text-xs", "font-mono", "text-gray-100", "bg-gray-800", "whitespace-pre",
+                if props.language.is_some() { "rounded-b" } else { "rounded" },
+            )}>
+                <code>{props.code.clone()}</code>
+            </pre>
+            <button
+                type="button"
+                {onclick}
+                aria-label="Copy code"
+                class={classes!(
+                    "absolute", "top-1", "right-1", "px-2", "py-0.5", "text-xs", "text-gray-200", "bg-gray-700",
+                    "rounded", "hover:bg-gray-600", "focus:opacity-100", "group-hover/code:opacity-100",
+                    if *copied { "opacity-100" } else { "opacity-0" },
+                )}
+            >
+                {if *copied { "Copied" } else { "Copy" }}
+            </button>
+        </div>
+    }
+}
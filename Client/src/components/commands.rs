@@ -0,0 +1,71 @@
+use crate::utils::command::{parse_command, Command};
+
+/// What a command handler wants done once it has run.
+#[derive(Debug, PartialEq)]
+pub enum CommandOutput {
+    /// Sent to the room as a chat message from us, in place of the command.
+    Send(String),
+    /// Shown only to us, as a notice in the message list.
+    Local(String),
+    /// Handled, with nothing to send or show.
+    Done,
+}
+
+/// What handlers may need to know besides the command itself.
+pub struct CommandContext<'a> {
+    /// Our own username.
+    pub me: &'a str,
+}
+
+/// Handles one slash command. Closures of the same shape are handlers too.
+pub trait CommandHandler {
+    fn run(&self, command: &Command, cx: &CommandContext) -> CommandOutput;
+}
+
+impl<F> CommandHandler for F
+where
+    F: Fn(&Command, &CommandContext) -> CommandOutput,
+{
+    fn run(&self, command: &Command, cx: &CommandContext) -> CommandOutput {
+        self(command, cx)
+    }
+}
+
+/// The slash commands `Chat` handles itself instead of sending, for bots
+/// and integrations. Commands nobody registered go to the server as typed.
+#[derive(Default)]
+pub struct Commands {
+    handlers: Vec<(String, Box<dyn CommandHandler>)>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles `/name` with `handler`. Names match regardless of case, and
+    /// registering a name again replaces its handler.
+    pub fn with_command(mut self, name: &str, handler: impl CommandHandler + 'static) -> Self {
+        let name = name.to_lowercase();
+        self.handlers.retain(|(n, _)| *n != name);
+        self.handlers.push((name, Box::new(handler)));
+        self
+    }
+
+    /// Runs the handler for `text`, or returns `None` when it isn't a
+    /// registered command.
+    pub fn run(&self, text: &str, cx: &CommandContext) -> Option<CommandOutput> {
+        let command = parse_command(text)?;
+        let name = command.name.to_lowercase();
+        let (_, handler) = self.handlers.iter().find(|(n, _)| *n == name)?;
+        Some(handler.run(&command, cx))
+    }
+}
+
+// Handlers can't be compared, so props only see a change when a different
+// registry is passed in.
+impl PartialEq for Commands {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
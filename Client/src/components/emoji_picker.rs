@@ -0,0 +1,137 @@
+use web_sys::{HtmlInputElement, KeyboardEvent};
+use yew::functional::*;
+use yew::prelude::*;
+
+use crate::utils::emoji::{move_selection, search, GridMove};
+
+// Matches the grid's `grid-cols-8`.
+const COLUMNS: usize = 8;
+
+#[derive(Properties, PartialEq)]
+pub struct EmojiPickerProps {
+    /// Called with the chosen emoji.
+    pub onpick: Callback<&'static str>,
+    pub onclose: Callback<()>,
+}
+
+/// A grid of emoji with a search field that keeps focus: typing filters by
+/// shortcode, the arrow keys move the selection, Enter picks it and Escape
+/// closes the picker.
+#[function_component(EmojiPicker)]
+pub fn emoji_picker(props: &EmojiPickerProps) -> Html {
+    let filter = use_state(String::new);
+    let selected = use_state(|| 0usize);
+    let search_input = use_node_ref();
+    {
+        let search_input = search_input.clone();
+        use_effect_with_deps(
+            move |_| {
+                if let Some(input) = search_input.cast::<HtmlInputElement>() {
+                    let _ = input.focus();
+                }
+                || ()
+            },
+            (),
+        );
+    }
+
+    let matches = search(&filter);
+    let oninput = {
+        let filter = filter.clone();
+        let selected = selected.clone();
+        Callback::from(move |e: InputEvent| {
+            filter.set(e.target_unchecked_into::<HtmlInputElement>().value());
+            selected.set(0);
+        })
+    };
+    let onkeydown = {
+        let matches = matches.clone();
+        let selected = selected.clone();
+        let onpick = props.onpick.clone();
+        let onclose = props.onclose.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if let Some(step) = GridMove::from_key(&e.key()) {
+                e.prevent_default();
+                selected.set(move_selection(*selected, matches.len(), COLUMNS, step));
+                return;
+            }
+            match e.key().as_str() {
+                "Enter" => {
+                    e.prevent_default();
+                    if let Some(&(_, emoji)) = matches.get(*selected) {
+                        onpick.emit(emoji);
+                    }
+                }
+                "Escape" => {
+                    e.prevent_default();
+                    onclose.emit(());
+                }
+                _ => {}
+            }
+        })
+    };
+
+    let option_id = |i: usize| format!("yc-emoji-{}", i);
+    let active = matches.get(*selected).map(|_| option_id(*selected));
+    let grid = if matches.is_empty() {
+        html! { <p class="px-1 py-2 text-sm text-gray-400">{"No matching emoji"}</p> }
+    } else {
+        html! {
+            <div id="yc-emoji-grid" role="listbox" aria-label="Emoji" class="grid grid-cols-8 gap-1">
+                {
+                    matches.iter().enumerate().map(|(i, &(code, emoji))| {
+                        let onclick = props.onpick.reform(move |_| emoji);
+                        let onmouseenter = {
+                            let selected = selected.clone();
+                            Callback::from(move |_| selected.set(i))
+                        };
+                        html! {
+                            <button
+                                id={option_id(i)}
+                                type="button"
+                                role="option"
+                                tabindex="-1"
+                                title={format!(":{}:", code)}
+                                aria-label={code}
+                                aria-selected={(i == *selected).to_string()}
+                                // Keeps focus in the search field.
+                                onmousedown={Callback::from(|e: MouseEvent| e.prevent_default())}
+                                {onclick}
+                                {onmouseenter}
+                                class={classes!(
+                                    "w-8", "h-8", "text-lg", "rounded",
+                                    if i == *selected { "bg-blue-100 ring-2 ring-blue-400" } else { "hover:bg-gray-100" },
+                                )}
+                            >
+                                {emoji}
+                            </button>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        }
+    };
+
+    html! {
+        <div class="absolute bottom-full left-0 z-30 mb-2 w-72 p-2 bg-white border rounded-lg shadow-lg">
+            <input
+                ref={search_input}
+                type="text"
+                value={(*filter).clone()}
+                {oninput}
+                {onkeydown}
+                placeholder="Search emoji"
+                aria-label="Search emoji"
+                role="combobox"
+                aria-expanded="true"
+                aria-controls="yc-emoji-grid"
+                aria-activedescendant={active}
+                class="w-full px-2 py-1 mb-2 text-sm border rounded focus:outline-none focus:ring-2 focus:ring-[color:var(--yc-primary)]"
+            />
+            {grid}
+            <p class="h-4 mt-1 text-xs text-gray-400 truncate">
+                {matches.get(*selected).map(|(code, _)| format!(":{}:", code)).unwrap_or_default()}
+            </p>
+        </div>
+    }
+}
@@ -0,0 +1,87 @@
+use gloo_events::EventListener;
+use gloo_timers::callback::Interval;
+use wasm_bindgen::JsCast;
+use web_sys::KeyboardEvent;
+use yew::prelude::*;
+
+use crate::services::frame_log::{frames, Direction};
+use crate::utils::time::{format_time, TimeFormat};
+
+pub enum Msg {
+    Toggle,
+    Refresh,
+}
+
+/// Developer overlay listing the most recent raw protocol frames, toggled
+/// with Ctrl+Shift+D. Only compiled with the `debug-overlay` feature.
+pub struct FrameLog {
+    refresh: Option<Interval>,
+    _keydown_listener: EventListener,
+}
+
+fn pretty(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .and_then(|v| serde_json::to_string_pretty(&v))
+        .unwrap_or_else(|_| body.to_string())
+}
+
+impl Component for FrameLog {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let link = ctx.link().clone();
+        let keydown_listener = EventListener::new(&gloo_utils::document(), "keydown", move |e| {
+            let e = e.dyn_ref::<KeyboardEvent>().unwrap();
+            if e.ctrl_key() && e.shift_key() && e.key().eq_ignore_ascii_case("d") {
+                link.send_message(Msg::Toggle)
+            }
+        });
+        Self {
+            refresh: None,
+            _keydown_listener: keydown_listener,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Toggle => {
+                self.refresh = match self.refresh.take() {
+                    Some(_) => None,
+                    None => {
+                        let link = ctx.link().clone();
+                        Some(Interval::new(1_000, move || {
+                            link.send_message(Msg::Refresh)
+                        }))
+                    }
+                };
+                true
+            }
+            Msg::Refresh => self.refresh.is_some(),
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        if self.refresh.is_none() {
+            return html! {};
+        }
+        html! {
+            <div class="fixed top-4 right-4 z-40 w-96 max-h-96 overflow-y-auto p-2 space-y-2 text-xs font-mono bg-gray-900 bg-opacity-95 rounded shadow">
+                {
+                    frames().into_iter().rev().map(|frame| {
+                        let (arrow, color) = match frame.direction {
+                            Direction::Incoming => ("⬇", "text-green-300"),
+                            Direction::Outgoing => ("⬆", "text-yellow-300"),
+                        };
+                        html! {
+                            <div class={color}>
+                                <p class="text-gray-400">{format!("{} {}", arrow, format_time(frame.time, TimeFormat::H24))}</p>
+                                <pre class="whitespace-pre-wrap break-all">{pretty(&frame.body)}</pre>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        }
+    }
+}
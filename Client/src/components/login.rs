@@ -5,10 +5,11 @@ use yew_router::prelude::*;
 
 use crate::Route;
 use crate::User;
+use crate::utils::username::validate_username;
 
 #[function_component(Login)]
 pub fn login() -> Html {
-    let username = use_state(|| String::new());
+    let username = use_state(String::new);
     let user = use_context::<User>().expect("No context found.");
 
     let oninput = {
@@ -23,7 +24,11 @@ pub fn login() -> Html {
     let onclick = {
         let username = username.clone();
         let user = user.clone();
-        Callback::from(move |_| *user.username.borrow_mut() = (*username).clone())
+        Callback::from(move |_| {
+            if let Ok(name) = validate_username(&username) {
+                *user.username.borrow_mut() = name;
+            }
+        })
     };
     
     html! {
@@ -31,7 +36,7 @@ pub fn login() -> Html {
             <div class="container mx-auto flex flex-col justify-center items-center	">
                 <form class="m-4 flex">
                     <input {oninput} class="rounded-l-lg p-4 border-t mr-0 border-b border-l text-gray-800 border-gray-200 bg-white" placeholder="Username"/>
-                    <Link<Route> to={Route::Chat}> <button {onclick} disabled={username.len()<1} class="px-8 rounded-r-lg bg-violet-600	  text-white font-bold p-4 uppercase border-violet-600 border-t border-b border-r" >{"Go Chatting!"}</button></Link<Route>>
+                    <Link<Route> to={Route::Chat}> <button {onclick} disabled={validate_username(&username).is_err()} class="px-8 rounded-r-lg bg-violet-600	  text-white font-bold p-4 uppercase border-violet-600 border-t border-b border-r" >{"Go Chatting!"}</button></Link<Route>>
                 </form>
             </div>
         </div>
@@ -1,2 +1,11 @@
+pub mod avatar;
 pub mod chat;
-pub mod login;
\ No newline at end of file
+pub mod code_block;
+pub mod commands;
+pub mod emoji_picker;
+#[cfg(feature = "debug-overlay")]
+pub mod frame_log;
+pub mod login;
+pub mod render;
+pub mod settings;
+pub mod toasts;
\ No newline at end of file
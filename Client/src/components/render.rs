@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+
+use yew::prelude::*;
+
+use crate::state::chat::UserProfile;
+use crate::utils::tokenize::Segment;
+
+/// What a renderer turned a segment into.
+#[derive(Debug, PartialEq)]
+pub enum Rendered {
+    /// Shown in the flow of the message text.
+    Inline(Html),
+    /// An image URL, taken out of the text and shown in the gallery below it.
+    Image(String),
+}
+
+/// What renderers may need to know besides the segment itself.
+pub struct RenderContext<'a> {
+    /// Our own username, so mentions of us can stand out.
+    pub me: &'a str,
+    /// Who is in the room, or `None` while the roster hasn't loaded, when
+    /// every mention counts as present.
+    pub roster: Option<&'a [UserProfile]>,
+}
+
+impl RenderContext<'_> {
+    /// Whether the user `name` is in the room, ignoring ASCII case.
+    pub fn is_present(&self, name: &str) -> bool {
+        name.eq_ignore_ascii_case(self.me)
+            || self.roster.is_none_or(|users| users.iter().any(|u| u.id.eq_ignore_ascii_case(name)))
+    }
+}
+
+/// Renders some kinds of message segment. `Renderers` asks each renderer in
+/// turn and uses the first answer; `None` leaves the segment to the rest.
+pub trait MessageRenderer {
+    fn render(&self, segment: &Segment, cx: &RenderContext) -> Option<Rendered>;
+}
+
+/// Image URLs become gallery thumbnails.
+pub struct Images;
+
+impl MessageRenderer for Images {
+    fn render(&self, segment: &Segment, _cx: &RenderContext) -> Option<Rendered> {
+        match segment {
+            Segment::Image(url) => Some(Rendered::Image(url.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Markdown links open in a new tab.
+pub struct Links;
+
+impl MessageRenderer for Links {
+    fn render(&self, segment: &Segment, _cx: &RenderContext) -> Option<Rendered> {
+        match segment {
+            Segment::Link { label, url } => Some(Rendered::Inline(html! {
+                <a href={url.to_string()} target="_blank" rel="noopener noreferrer" class="text-blue-600 hover:underline">{label.to_string()}</a>
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// `@name` mentions, highlighted more strongly when they name us and muted
+/// when they name someone who isn't in the room.
+pub struct Mentions;
+
+impl MessageRenderer for Mentions {
+    fn render(&self, segment: &Segment, cx: &RenderContext) -> Option<Rendered> {
+        let name = match segment {
+            Segment::Mention(name) => name,
+            _ => return None,
+        };
+        if !cx.is_present(name) {
+            return Some(Rendered::Inline(html! {
+                <span title={format!("{} isn't in the room", name)} class="px-1 rounded bg-gray-100 text-gray-400">
+                    {format!("@{}", name)}
+                </span>
+            }));
+        }
+        let class = if name.eq_ignore_ascii_case(cx.me) {
+            "px-1 rounded bg-yellow-300 text-yellow-900 font-semibold"
+        } else {
+            "px-1 rounded bg-blue-50 text-blue-700"
+        };
+        Some(Rendered::Inline(
+            html! { <span {class}>{format!("@{}", name)}</span> },
+        ))
+    }
+}
+
+/// `@everyone` and `@here`.
+pub struct Broadcasts;
+
+impl MessageRenderer for Broadcasts {
+    fn render(&self, segment: &Segment, _cx: &RenderContext) -> Option<Rendered> {
+        match segment {
+            Segment::Broadcast(name) => Some(Rendered::Inline(html! {
+                <span class="px-1 rounded bg-red-100 text-red-700 font-bold">{format!("@{}", name)}</span>
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Markdown bold, italic and inline code.
+pub struct Emphasis;
+
+impl MessageRenderer for Emphasis {
+    fn render(&self, segment: &Segment, _cx: &RenderContext) -> Option<Rendered> {
+        let html = match segment {
+            Segment::Bold(text) => html! { <strong>{text.to_string()}</strong> },
+            Segment::Italic(text) => html! { <em>{text.to_string()}</em> },
+            Segment::Code(text) => html! {
+                <code class="px-1 rounded bg-gray-100 font-mono text-[0.9em]">{text.to_string()}</code>
+            },
+            _ => return None,
+        };
+        Some(Rendered::Inline(html))
+    }
+}
+
+/// Renders the whole body of messages with a content type `Chat` has no
+/// built-in handling for, registered with `Renderers::with_content_type`.
+pub trait ContentRenderer {
+    fn render(&self, body: &str, cx: &RenderContext) -> Html;
+}
+
+/// How a message's body is shown, by its declared content type.
+pub enum Content<'a> {
+    /// `text/markdown`, and messages without a content type: the text goes
+    /// through the segment renderers.
+    Markdown,
+    /// `text/plain`: shown exactly as sent.
+    Plain,
+    /// `image/*`: the body is the image URL.
+    Image,
+    Custom(&'a dyn ContentRenderer),
+    /// Anything else, shown as a placeholder with the raw body on request.
+    Unsupported,
+}
+
+/// The renderers `Chat` applies to message text, in order. Segments none of
+/// them claim are shown as the plain text they were written as.
+pub struct Renderers {
+    segments: Vec<Box<dyn MessageRenderer>>,
+    // Content type patterns, such as `application/json` or `video/*`, with
+    // their renderers, checked before the built-in types.
+    content_types: Vec<(String, Box<dyn ContentRenderer>)>,
+}
+
+impl Renderers {
+    pub fn new(renderers: Vec<Box<dyn MessageRenderer>>) -> Self {
+        Self {
+            segments: renderers,
+            content_types: Vec::new(),
+        }
+    }
+
+    /// Renders messages whose content type matches `pattern` with
+    /// `renderer`. A pattern ending in `/*` covers a whole family of types.
+    pub fn with_content_type(mut self, pattern: &str, renderer: Box<dyn ContentRenderer>) -> Self {
+        self.content_types
+            .push((pattern.to_ascii_lowercase(), renderer));
+        self
+    }
+
+    pub fn render(&self, segment: &Segment, cx: &RenderContext) -> Option<Rendered> {
+        self.segments
+            .iter()
+            .find_map(|renderer| renderer.render(segment, cx))
+    }
+
+    /// How to show a message declared as `content_type`. Parameters such as
+    /// `; charset=utf-8` and letter case don't matter.
+    pub fn content(&self, content_type: Option<&str>) -> Content<'_> {
+        let mime = match content_type {
+            Some(t) => essence(t),
+            None => return Content::Markdown,
+        };
+        if let Some((_, renderer)) = self
+            .content_types
+            .iter()
+            .find(|(pattern, _)| matches_pattern(pattern, &mime))
+        {
+            return Content::Custom(renderer.as_ref());
+        }
+        match mime.as_str() {
+            "text/markdown" => Content::Markdown,
+            "text/plain" => Content::Plain,
+            m if m.starts_with("image/") => Content::Image,
+            _ => Content::Unsupported,
+        }
+    }
+}
+
+// `Text/Plain; charset=utf-8` as `text/plain`.
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn matches_pattern(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(family) => mime.split('/').next() == Some(family),
+        None => pattern == mime,
+    }
+}
+
+// Renderers can't be compared, so props only see a change when a different
+// registry is passed in.
+impl PartialEq for Renderers {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Default for Renderers {
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(Images),
+            Box::new(Links),
+            Box::new(Mentions),
+            Box::new(Broadcasts),
+            Box::new(Emphasis),
+        ])
+    }
+}
+
+/// A segment as it was typed, for when no renderer claims it.
+pub fn plain_text<'a>(segment: &Segment<'a>) -> Cow<'a, str> {
+    match *segment {
+        Segment::Text(t) | Segment::Image(t) => Cow::Borrowed(t),
+        Segment::Link { label, url } => Cow::Owned(format!("[{}]({})", label, url)),
+        Segment::Mention(name) | Segment::Broadcast(name) => Cow::Owned(format!("@{}", name)),
+        Segment::Bold(text) => Cow::Owned(format!("**{}**", text)),
+        Segment::Italic(text) => Cow::Owned(format!("*{}*", text)),
+        Segment::Code(text) => Cow::Owned(format!("`{}`", text)),
+    }
+}
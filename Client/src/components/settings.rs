@@ -0,0 +1,296 @@
+use web_sys::HtmlInputElement;
+use yew::functional::*;
+use yew::prelude::*;
+
+use crate::services::settings::{AvatarShape, ImageSize, NotificationSound, SendKey, Settings, SettingsContext, TimestampDisplay};
+use crate::services::sound;
+use crate::utils::time::TimeFormat;
+use crate::utils::url::is_image_url;
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsPanelProps {
+    pub onclose: Callback<()>,
+}
+
+// Renders one button per option; clicking one writes it back through `apply`.
+fn segmented<T: Copy + PartialEq + 'static>(
+    settings: &SettingsContext,
+    options: &[(T, &'static str)],
+    current: T,
+    apply: fn(&mut Settings, T),
+) -> Html {
+    options
+        .iter()
+        .map(|&(value, label)| {
+            let settings = settings.clone();
+            let onclick = Callback::from(move |_| {
+                let mut updated = (*settings).clone();
+                apply(&mut updated, value);
+                settings.dispatch(updated);
+            });
+            let state = if value == current {
+                "bg-blue-600 text-white"
+            } else {
+                "bg-gray-100 text-gray-700 hover:bg-gray-200"
+            };
+            html! {
+                <button {onclick} class={classes!("px-3", "py-1", "text-sm", state)}>{label}</button>
+            }
+        })
+        .collect()
+}
+
+#[function_component(SettingsPanel)]
+pub fn settings_panel(props: &SettingsPanelProps) -> Html {
+    let settings = use_context::<SettingsContext>().expect("No settings context found.");
+
+    let onclose = {
+        let onclose = props.onclose.clone();
+        Callback::from(move |_| onclose.emit(()))
+    };
+
+    let avatar_input = use_node_ref();
+    let avatar_error = use_state(|| false);
+    let save_avatar = {
+        let settings = settings.clone();
+        let avatar_input = avatar_input.clone();
+        let avatar_error = avatar_error.clone();
+        Callback::from(move |_| {
+            let value = avatar_input
+                .cast::<HtmlInputElement>()
+                .map(|input| input.value().trim().to_string())
+                .unwrap_or_default();
+            // An empty field goes back to the generated avatar.
+            if !value.is_empty() && !is_image_url(&value) {
+                avatar_error.set(true);
+                return;
+            }
+            avatar_error.set(false);
+            let mut updated = (*settings).clone();
+            updated.avatar_url = Some(value).filter(|v| !v.is_empty());
+            settings.dispatch(updated);
+        })
+    };
+
+    let words_input = use_node_ref();
+    let save_words = {
+        let settings = settings.clone();
+        let words_input = words_input.clone();
+        Callback::from(move |_| {
+            let value = words_input
+                .cast::<HtmlInputElement>()
+                .map(|input| input.value())
+                .unwrap_or_default();
+            let mut updated = (*settings).clone();
+            updated.filtered_words = value
+                .split(',')
+                .map(str::trim)
+                .filter(|w| !w.is_empty())
+                .map(String::from)
+                .collect();
+            settings.dispatch(updated);
+        })
+    };
+
+    let preview_sound = {
+        let sound = settings.notification_sound;
+        Callback::from(move |_| sound::play(sound))
+    };
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-30">
+            <div class="w-96 bg-white rounded-lg shadow-lg">
+                <div class="flex items-center justify-between px-4 py-3 border-b">
+                    <span class="text-lg font-semibold text-gray-700">{"⚙️ Settings"}</span>
+                    <button onclick={onclose} class="text-gray-400 hover:text-gray-700">{"✕"}</button>
+                </div>
+                <div class="p-4 space-y-4">
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Time format"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(TimeFormat::H12, TimeFormat::H12.label()), (TimeFormat::H24, TimeFormat::H24.label())],
+                                settings.time_format,
+                                |s, v| s.time_format = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Timestamps"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(TimestampDisplay::Always, "Always"), (TimestampDisplay::Hover, "On hover"), (TimestampDisplay::Hidden, "Hidden")],
+                                settings.timestamps,
+                                |s, v| s.timestamps = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Send with"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(SendKey::Enter, "Enter"), (SendKey::CtrlEnter, "Ctrl+Enter")],
+                                settings.send_key,
+                                |s, v| s.send_key = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Messages from departed users"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(false, "Dim"), (true, "Hide")],
+                                settings.hide_departed,
+                                |s, v| s.hide_departed = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Image previews"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(ImageSize::Small, "Small"), (ImageSize::Medium, "Medium"), (ImageSize::Large, "Large")],
+                                settings.image_size,
+                                |s, v| s.image_size = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Connection latency"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(false, "Hide"), (true, "Show")],
+                                settings.show_latency,
+                                |s, v| s.show_latency = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Contrast"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(false, "Normal"), (true, "High")],
+                                settings.high_contrast,
+                                |s, v| s.high_contrast = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Avatars"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(true, "Images"), (false, "Initials")],
+                                settings.show_avatars,
+                                |s, v| s.show_avatars = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Avatar shape"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(AvatarShape::Circle, "Circle"), (AvatarShape::Rounded, "Rounded"), (AvatarShape::Square, "Square")],
+                                settings.avatar_shape,
+                                |s, v| s.avatar_shape = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Message sound"}</span>
+                        <div class="flex items-center space-x-2">
+                            <div class="flex rounded overflow-hidden border">
+                                {segmented(
+                                    &settings,
+                                    &[NotificationSound::None, NotificationSound::Chime, NotificationSound::Pop, NotificationSound::Ding]
+                                        .map(|s| (s, s.label())),
+                                    settings.notification_sound,
+                                    |s, v| s.notification_sound = v,
+                                )}
+                            </div>
+                            // Previews play even while sounds are muted.
+                            <button
+                                onclick={preview_sound}
+                                disabled={settings.notification_sound.file().is_none()}
+                                title="Preview"
+                                aria-label="Preview the message sound"
+                                class="text-sm text-gray-500 hover:text-gray-800 disabled:opacity-30"
+                            >
+                                {"▶"}
+                            </button>
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Sounds"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(false, "On"), (true, "Muted")],
+                                settings.mute_sounds,
+                                |s, v| s.mute_sounds = v,
+                            )}
+                        </div>
+                    </div>
+                    <div class="flex items-center justify-between">
+                        <span class="text-sm text-gray-700">{"Word filter"}</span>
+                        <div class="flex rounded overflow-hidden border">
+                            {segmented(
+                                &settings,
+                                &[(false, "Off"), (true, "On")],
+                                settings.filter_words,
+                                |s, v| s.filter_words = v,
+                            )}
+                        </div>
+                    </div>
+                    {
+                        if settings.filter_words {
+                            html! {
+                                <div class="space-y-1">
+                                    <span class="text-sm text-gray-700">{"Words to mask, separated by commas"}</span>
+                                    <div class="flex space-x-2">
+                                        <input
+                                            ref={words_input}
+                                            value={settings.filtered_words.join(", ")}
+                                            class="flex-grow px-2 py-1 text-sm border rounded"
+                                        />
+                                        <button onclick={save_words} class="px-3 py-1 text-sm text-white bg-blue-600 rounded hover:bg-blue-700">{"Save"}</button>
+                                    </div>
+                                    <p class="text-xs text-gray-400">{"Only changes what you see; others still get the original."}</p>
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    <div class="space-y-1">
+                        <span class="text-sm text-gray-700">{"Avatar image URL"}</span>
+                        <div class="flex space-x-2">
+                            <input
+                                ref={avatar_input}
+                                value={settings.avatar_url.clone().unwrap_or_default()}
+                                placeholder="https://example.com/me.png"
+                                class="flex-grow px-2 py-1 text-sm border rounded"
+                            />
+                            <button onclick={save_avatar} class="px-3 py-1 text-sm text-white bg-blue-600 rounded hover:bg-blue-700">{"Save"}</button>
+                        </div>
+                        {
+                            if *avatar_error {
+                                html! { <p class="text-xs text-red-500">{"Enter an http(s) link to a .png, .jpg, .gif, .webp or .svg image"}</p> }
+                            } else {
+                                html! {}
+                            }
+                        }
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,97 @@
+use gloo_timers::callback::Timeout;
+use yew::prelude::*;
+use yew_agent::{Bridge, Bridged};
+
+use crate::services::toast::{Severity, Toast, ToastBus};
+
+// Older toasts are dropped once this many are on screen.
+const MAX_TOASTS: usize = 4;
+
+pub enum Msg {
+    Show(Toast),
+    Dismiss(u32),
+}
+
+struct Entry {
+    id: u32,
+    toast: Toast,
+    _timeout: Timeout,
+}
+
+/// Renders toasts sent through `services::toast::notify` as a stack in the
+/// bottom-right corner, each dismissing itself after its duration.
+pub struct ToastStack {
+    entries: Vec<Entry>,
+    next_id: u32,
+    _bus: Box<dyn Bridge<ToastBus>>,
+}
+
+impl Component for ToastStack {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            entries: vec![],
+            next_id: 0,
+            _bus: ToastBus::bridge(ctx.link().callback(Msg::Show)),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Show(toast) => {
+                let id = self.next_id;
+                self.next_id += 1;
+                let link = ctx.link().clone();
+                let timeout = Timeout::new(toast.duration_ms, move || {
+                    link.send_message(Msg::Dismiss(id))
+                });
+                if self.entries.len() == MAX_TOASTS {
+                    self.entries.remove(0);
+                }
+                self.entries.push(Entry {
+                    id,
+                    toast,
+                    _timeout: timeout,
+                });
+                true
+            }
+            Msg::Dismiss(id) => {
+                let before = self.entries.len();
+                self.entries.retain(|e| e.id != id);
+                self.entries.len() != before
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div aria-live="polite" class="fixed bottom-4 right-4 z-50 flex flex-col items-end space-y-2">
+                {
+                    self.entries.iter().map(|entry| {
+                        let id = entry.id;
+                        let colors = match entry.toast.severity {
+                            Severity::Info => "bg-gray-800 text-white",
+                            Severity::Success => "bg-green-600 text-white",
+                            Severity::Warning => "bg-yellow-400 text-yellow-900",
+                            Severity::Error => "bg-red-600 text-white",
+                        };
+                        html! {
+                            <div key={id} class={classes!("flex", "items-center", "space-x-3", "max-w-sm", "px-4", "py-2", "rounded-lg", "shadow-lg", "text-sm", colors)}>
+                                <span>{entry.toast.text.clone()}</span>
+                                <button
+                                    onclick={ctx.link().callback(move |_| Msg::Dismiss(id))}
+                                    title="Dismiss"
+                                    class="opacity-75 hover:opacity-100"
+                                >
+                                    {"✕"}
+                                </button>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        }
+    }
+}
@@ -1,9 +1,32 @@
 #![recursion_limit = "512"]
+// `html!` in yew 0.19 expands to code that trips these lints at every call site.
+#![allow(clippy::let_unit_value, clippy::unnecessary_operation)]
+
+// `log::debug!` for paths every frame goes through. Builds without the
+// `verbose-logging` feature compile it away, arguments and all; warnings and
+// errors go through `log` directly and are always kept. Defined before the
+// modules so they can all use it.
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logging") {
+            log::debug!($($arg)*);
+        }
+    };
+}
 
 mod components;
-mod services;
+pub mod services;
+pub mod state;
+pub mod utils;
 use components::login::Login;
-use components::chat::Chat;
+use components::toasts::ToastStack;
+// For apps embedding the chat screen. It expects `User` and `SettingsContext`
+// contexts above it, as `Main` provides.
+pub use components::chat::{Chat, ChatHandle, ChatProps};
+pub use components::commands;
+pub use components::render;
+pub use services::theme::Theme;
+use services::settings::{Settings, SettingsContext};
 use wasm_bindgen::prelude::*;
 use yew::functional::*;
 use yew::prelude::*;
@@ -43,17 +66,21 @@ fn switch(selected_route: &Route) -> Html {
 fn app() -> Html {
     let ctx = use_state(|| {
         Rc::new(UserInner {
-            username: RefCell::new("initial".into()),
+            username: RefCell::new(String::new()),
         })
     });
+    let settings = use_reducer(Settings::load);
 
     html! {
         <ContextProvider<User> context={(*ctx).clone()}>
-            <BrowserRouter>
-                <div class="flex w-screen h-screen">
-                    <Switch<Route> render={Switch::render(switch)}/>
-                </div>
-            </BrowserRouter>
+            <ContextProvider<SettingsContext> context={settings}>
+                <BrowserRouter>
+                    <div class="yc-app flex w-screen">
+                        <Switch<Route> render={Switch::render(switch)}/>
+                    </div>
+                </BrowserRouter>
+                <ToastStack />
+            </ContextProvider<SettingsContext>>
         </ContextProvider<User>>
     }
 }
@@ -0,0 +1,17 @@
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Puts `text` on the clipboard.
+pub async fn write_text(text: &str) -> Result<(), JsValue> {
+    // `navigator.clipboard` is behind web-sys's unstable APIs, so it is
+    // looked up dynamically.
+    let navigator = gloo_utils::window().navigator();
+    let clipboard = js_sys::Reflect::get(&navigator, &"clipboard".into())?;
+    if clipboard.is_undefined() {
+        return Err(JsValue::from_str("the clipboard API is unavailable"));
+    }
+    let write: js_sys::Function =
+        js_sys::Reflect::get(&clipboard, &"writeText".into())?.dyn_into()?;
+    let promise: js_sys::Promise = write.call1(&clipboard, &text.into())?.dyn_into()?;
+    JsFuture::from(promise).await.map(|_| ())
+}
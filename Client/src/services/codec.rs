@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::services::compression::Compressed;
+use crate::services::protocol::{MsgTypes, WebSocketMessage};
+use crate::services::websocket::WsError;
+
+/// What actually crosses the socket: a text or a binary WebSocket frame.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Turns protocol frames into socket text and back, so the client can talk to
+/// servers that don't speak the JSON protocol.
+pub trait Codec {
+    fn encode(&self, msg: &WebSocketMessage) -> Result<String, WsError>;
+    fn decode(&self, raw: &str) -> Result<WebSocketMessage, String>;
+
+    /// Codecs with a compact binary form return it here; the default sends
+    /// text frames.
+    fn encode_binary(&self, _msg: &WebSocketMessage) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Binary frames are UTF-8 text unless the codec says otherwise.
+    fn decode_binary(&self, bytes: &[u8]) -> Result<WebSocketMessage, String> {
+        let raw = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+        self.decode(raw)
+    }
+
+    fn encode_payload(&self, msg: &WebSocketMessage) -> Result<Payload, WsError> {
+        match self.encode_binary(msg) {
+            Some(bytes) => Ok(Payload::Binary(bytes)),
+            None => self.encode(msg).map(Payload::Text),
+        }
+    }
+
+    fn decode_payload(&self, payload: &Payload) -> Result<WebSocketMessage, String> {
+        match payload {
+            Payload::Text(raw) => self.decode(raw),
+            Payload::Binary(bytes) => self.decode_binary(bytes),
+        }
+    }
+}
+
+/// The codec selected at build time: the line protocol with the
+/// `line-protocol` feature, JSON otherwise. Large JSON frames are compressed,
+/// which the bundled server understands, unless the `no-compression`
+/// feature turns that off; line servers always get plain text.
+pub fn configured() -> Box<dyn Codec> {
+    if cfg!(feature = "line-protocol") {
+        Box::new(LineCodec)
+    } else if cfg!(feature = "no-compression") {
+        Box::new(JsonCodec)
+    } else {
+        Box::new(Compressed::new(JsonCodec))
+    }
+}
+
+/// `serde_json::to_string` with the failure as a `WsError::Serialize`, for
+/// frames and for the JSON payloads nested inside them.
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, WsError> {
+    serde_json::to_string(value).map_err(|e| WsError::Serialize(e.to_string()))
+}
+
+/// The protocol spoken by the bundled server.
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode(&self, msg: &WebSocketMessage) -> Result<String, WsError> {
+        to_json(msg)
+    }
+
+    fn decode(&self, raw: &str) -> Result<WebSocketMessage, String> {
+        serde_json::from_str(raw).map_err(|e| e.to_string())
+    }
+}
+
+/// One frame per line: the message type, a space, then the data with
+/// backslashes and newlines escaped. Two incoming types are shaped
+/// differently so that minimal servers need no JSON at all:
+///
+/// - `users alice bob` lists the ids of everyone in the room.
+/// - `message alice hello there` is a chat message from `alice`.
+pub struct LineCodec;
+
+impl Codec for LineCodec {
+    fn encode(&self, msg: &WebSocketMessage) -> Result<String, WsError> {
+        let name = serde_json::to_value(&msg.message_type)
+            .map_err(|e| WsError::Serialize(e.to_string()))?;
+        let name = name
+            .as_str()
+            .ok_or_else(|| WsError::Serialize(format!("message type {} is not a name", name)))?;
+        // Line servers have nowhere else to put attachments, so they go at
+        // the end of the text, where URLs still render.
+        let attachments = match (&msg.message_type, &msg.data_array) {
+            (MsgTypes::Message, Some(urls)) if !urls.is_empty() => urls.join(" "),
+            _ => String::new(),
+        };
+        let data = match (msg.data.as_deref().unwrap_or(""), attachments.as_str()) {
+            ("", "") if msg.data.is_none() => return Ok(name.to_string()),
+            (text, "") | ("", text) => text.to_string(),
+            (text, urls) => format!("{} {}", text, urls),
+        };
+        Ok(format!("{} {}", name, escape(&data)))
+    }
+
+    fn decode(&self, raw: &str) -> Result<WebSocketMessage, String> {
+        let line = raw.trim_end_matches(['\r', '\n']);
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let message_type: MsgTypes = serde_json::from_value(Value::String(name.to_string()))
+            .map_err(|_| format!("unknown message type {:?}", name))?;
+        let msg = match message_type {
+            MsgTypes::Users => WebSocketMessage {
+                data_array: Some(rest.split_whitespace().map(String::from).collect()),
+                ..WebSocketMessage::new(message_type, None)
+            },
+            MsgTypes::Message => {
+                let (from, text) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| "message line without a sender".to_string())?;
+                let data = serde_json::json!({ "from": from, "message": unescape(text) });
+                WebSocketMessage::new(message_type, Some(data.to_string()))
+            }
+            _ => WebSocketMessage::new(message_type, (!rest.is_empty()).then(|| unescape(rest))),
+        };
+        Ok(msg)
+    }
+}
+
+fn escape(data: &str) -> String {
+    data.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(data: &str) -> String {
+    let mut out = String::with_capacity(data.len());
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
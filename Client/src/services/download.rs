@@ -0,0 +1,44 @@
+use gloo_timers::callback::Timeout;
+use reqwasm::http::{Request, RequestMode};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+/// Offers `contents` to the user as a file download named `file_name`.
+pub fn save(file_name: &str, mime_type: &str, contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let blob =
+        Blob::new_with_str_sequence_and_options(&parts, BlobPropertyBag::new().type_(mime_type))?;
+    save_blob(file_name, &blob)
+}
+
+/// Fetches `url` and offers the response as a download named `file_name`.
+///
+/// The fetch is a CORS request, so it fails for hosts that don't allow
+/// cross-origin reads, as well as for network errors and non-2xx statuses.
+pub async fn fetch(url: &str, file_name: &str) -> Result<(), JsValue> {
+    let response = Request::get(url)
+        .mode(RequestMode::Cors)
+        .send()
+        .await
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    if !response.ok() {
+        return Err(JsValue::from_str(&format!("HTTP {}", response.status())));
+    }
+    let blob: Blob = JsFuture::from(response.as_raw().blob()?).await?.unchecked_into();
+    save_blob(file_name, &blob)
+}
+
+fn save_blob(file_name: &str, blob: &Blob) -> Result<(), JsValue> {
+    let url = Url::create_object_url_with_blob(blob)?;
+    let anchor: HtmlAnchorElement = gloo_utils::document().create_element("a")?.unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    // Some browsers only read the blob after the click handler returns.
+    Timeout::new(0, move || {
+        let _ = Url::revoke_object_url(&url);
+    })
+    .forget();
+    Ok(())
+}
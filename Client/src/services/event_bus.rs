@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use yew_agent::{Agent, AgentLink, Context, HandlerId};
+use yew::Callback;
+use yew_agent::{Agent, AgentLink, Bridge, Bridged, Context, HandlerId};
+
+use crate::services::codec::{self, Payload};
+use crate::services::protocol::WebSocketMessage;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum Request {
-    EventBusMsg(String),
+    EventBusMsg(Payload),
 }
 
 pub struct EventBus {
@@ -16,7 +20,7 @@ impl Agent for EventBus {
     type Reach = Context<Self>;
     type Message = ();
     type Input = Request;
-    type Output = String;
+    type Output = Payload;
 
     fn create(link: AgentLink<Self>) -> Self {
         Self {
@@ -44,4 +48,31 @@ impl Agent for EventBus {
     fn disconnected(&mut self, id: HandlerId) {
         self.subscribers.remove(&id);
     }
+}
+
+/// Frames from the server, decoded, for as long as this lives.
+///
+/// The bus bridge connects in `subscribe` and disconnects when the
+/// subscription is dropped, so a component keeps it in a field and it goes
+/// away with the component. `unsubscribe` ends it earlier.
+pub struct Subscription {
+    _bridge: Box<dyn Bridge<EventBus>>,
+}
+
+impl Subscription {
+    /// Stops delivery right away instead of when the owner is dropped.
+    pub fn unsubscribe(self) {}
+}
+
+/// Calls `on_frame` with every frame the socket receives, decoded with the
+/// configured codec. Frames that don't decode are logged and skipped.
+pub fn subscribe(on_frame: Callback<WebSocketMessage>) -> Subscription {
+    let codec = codec::configured();
+    let on_payload = Callback::from(move |payload: Payload| match codec.decode_payload(&payload) {
+        Ok(msg) => on_frame.emit(msg),
+        Err(e) => debug_log!("dropping unparseable frame: {:?}", e),
+    });
+    Subscription {
+        _bridge: EventBus::bridge(on_payload),
+    }
 }
\ No newline at end of file
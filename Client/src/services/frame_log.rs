@@ -0,0 +1,46 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use crate::services::codec::Payload;
+
+const MAX_FRAMES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub direction: Direction,
+    pub time: f64,
+    pub body: String,
+}
+
+thread_local! {
+    static FRAMES: RefCell<VecDeque<Frame>> = RefCell::new(VecDeque::with_capacity(MAX_FRAMES));
+}
+
+/// Remembers a raw frame, dropping the oldest once `MAX_FRAMES` are stored.
+pub fn record(direction: Direction, payload: &Payload) {
+    let body = match payload {
+        Payload::Text(s) => s.clone(),
+        Payload::Binary(b) => format!("<{} bytes> {}", b.len(), String::from_utf8_lossy(b)),
+    };
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        if frames.len() == MAX_FRAMES {
+            frames.pop_front();
+        }
+        frames.push_back(Frame {
+            direction,
+            time: js_sys::Date::now(),
+            body,
+        });
+    });
+}
+
+pub fn frames() -> Vec<Frame> {
+    FRAMES.with(|frames| frames.borrow().iter().cloned().collect())
+}
@@ -0,0 +1,41 @@
+use std::cell::RefCell;
+
+use crate::services::websocket::ConnectionState;
+
+/// Cheap client-side counters for diagnosing flaky deployments. Nothing reads
+/// them unless the metrics overlay is opened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub errors: u64,
+    pub reconnects: u64,
+    /// The average heartbeat round trip, in milliseconds.
+    pub rtt_ms: Option<f64>,
+    pub connection: ConnectionState,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            messages_sent: 0,
+            messages_received: 0,
+            errors: 0,
+            reconnects: 0,
+            rtt_ms: None,
+            connection: ConnectionState::Connecting,
+        }
+    }
+}
+
+thread_local! {
+    static METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
+}
+
+pub fn record(f: impl FnOnce(&mut Metrics)) {
+    METRICS.with(|m| f(&mut m.borrow_mut()));
+}
+
+pub fn snapshot() -> Metrics {
+    METRICS.with(|m| m.borrow().clone())
+}
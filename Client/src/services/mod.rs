@@ -1,2 +1,16 @@
 pub mod websocket;
-pub mod event_bus;
\ No newline at end of file
+pub mod event_bus;
+pub mod settings;
+pub mod protocol;
+pub mod codec;
+pub mod compression;
+pub mod download;
+pub mod clipboard;
+pub mod metrics;
+pub mod toast;
+pub mod sound;
+pub mod read_marker;
+pub mod scroll_position;
+pub mod theme;
+#[cfg(feature = "debug-overlay")]
+pub mod frame_log;
\ No newline at end of file
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MsgTypes {
+    Users,
+    Register,
+    /// Sent with the text in `data` and any attachment URLs in `dataArray`;
+    /// received with a `MessageData` in `data`.
+    Message,
+    Typing,
+    Edit,
+    Delete,
+    Profile,
+    History,
+    Notice,
+    /// A server-wide notice from the operators (maintenance, welcome
+    /// messages), with its plain text in `data`.
+    Announcement,
+    Error,
+    Pin,
+    Unpin,
+    /// The ids of all pinned messages in `dataArray`, oldest pin first, in
+    /// the order they should be listed. The server broadcasts the new order.
+    PinOrder,
+    /// Sent with a `MessageReply` in `data` and attachment URLs in
+    /// `dataArray`. The server broadcasts the reply as a `message` whose
+    /// `threadId` is set.
+    Reply,
+    /// Our presence, `away` or `active` in `data`. The server reflects it
+    /// in the roster.
+    Status,
+    /// Sent with the id of the newest message we have read in `data`;
+    /// received with a `ReadReceipt` in `data` for everyone who moves on.
+    Read,
+    /// Heartbeat with an id in `data`, answered by a `pong` with the same id.
+    Ping,
+    Pong,
+}
+
+/// How a frame shows up in the message list.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Category {
+    /// Rendered as a message from its sender.
+    #[default]
+    Chat,
+    /// Rendered as a centred notice between messages.
+    System,
+    /// Rendered as a highlighted banner across the message list.
+    Announcement,
+    /// Only updates state (roster, typing, edits, ...) and is never shown.
+    Silent,
+}
+
+impl MsgTypes {
+    /// Decides where a frame of this type ends up. Anything not listed is
+    /// `Silent`, so new control frames never leak into the message list.
+    pub fn category(&self) -> Category {
+        match self {
+            MsgTypes::Message => Category::Chat,
+            MsgTypes::Notice => Category::System,
+            MsgTypes::Announcement => Category::Announcement,
+            _ => Category::Silent,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketMessage {
+    pub message_type: MsgTypes,
+    pub data_array: Option<Vec<String>>,
+    pub data: Option<String>,
+    /// Our id for an outgoing `message` or `reply`, echoed back in its
+    /// `MessageData` so a resend can be recognised and dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// When an outgoing `message` should disappear, as a unix timestamp in
+    /// milliseconds. The server copies it into the `MessageData`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<f64>,
+}
+
+impl WebSocketMessage {
+    /// A frame carrying just `data`, with every optional field unset.
+    pub fn new(message_type: MsgTypes, data: Option<String>) -> Self {
+        Self {
+            message_type,
+            data_array: None,
+            data,
+            client_id: None,
+            expires_at: None,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct MessageData {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub from: String,
+    #[serde(default, rename = "displayName")]
+    pub display_name: Option<String>,
+    pub message: String,
+    #[serde(default)]
+    pub time: Option<f64>,
+    #[serde(default)]
+    pub edited: bool,
+    #[serde(default)]
+    pub deleted: bool,
+    /// What `message` holds, as a MIME type such as `text/plain` or
+    /// `image/png`. Unset means chat text with markdown.
+    #[serde(default, rename = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// The `clientId` the sender gave the message, if any.
+    #[serde(default, rename = "clientId", skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// The id of the message this replies to, which roots its thread.
+    /// Replies show in the thread panel instead of the message list.
+    #[serde(default, rename = "threadId", skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// When the message disappears, as a unix timestamp in milliseconds.
+    /// Unset means it stays.
+    #[serde(default, rename = "expiresAt", skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<f64>,
+    /// URLs sent along with the text: images show in the gallery, anything
+    /// else as a file link.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+    #[serde(skip)]
+    pub category: Category,
+    // Whether this arrived while we were away and hasn't been seen since.
+    #[serde(skip)]
+    pub unread: bool,
+    // Ids of identical messages sent right after this one, shown as "×2" etc.
+    #[serde(skip)]
+    pub duplicates: Vec<String>,
+}
+
+/// Payload of an `edit` frame in both directions: the id of the message to
+/// change and its new text.
+#[derive(Serialize, Deserialize)]
+pub struct MessageEdit {
+    pub id: String,
+    pub message: String,
+}
+
+/// Payload of an outgoing `reply` frame.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageReply {
+    pub thread_id: String,
+    pub message: String,
+}
+
+/// Reply to a `history` request, whose `data` is the id of the oldest message
+/// the client already has (or empty for the latest page).
+#[derive(Deserialize)]
+pub struct HistoryPage {
+    pub messages: Vec<MessageData>,
+    pub more: bool,
+}
+
+/// Payload of an `error` frame: why the server rejected the last request.
+#[derive(Deserialize)]
+pub struct ErrorData {
+    pub code: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// The client id of the rejected `message` or `reply`, when it was one.
+    #[serde(default, rename = "clientId")]
+    pub client_id: Option<String>,
+}
+
+impl ErrorData {
+    /// Whether the error rejects a message or reply we sent, as opposed to
+    /// an edit, a delete or some other request. Servers that don't name the
+    /// rejected send only say so through codes that nothing else can cause.
+    pub fn rejects_send(&self) -> bool {
+        self.client_id.is_some() || matches!(self.code.as_str(), "rate_limited" | "too_long")
+    }
+
+    /// Text to show the user. Known codes get a friendly explanation, others
+    /// fall back to the server's own message.
+    pub fn describe(&self) -> String {
+        let friendly = match self.code.as_str() {
+            "rate_limited" => "You're sending messages too quickly. Wait a moment and try again.",
+            "invalid_username" => "That username isn't allowed. Pick another one.",
+            "username_taken" => "Someone in the room already uses that username. Pick another one.",
+            "not_owner" => "You can only edit or delete your own messages.",
+            "too_long" => "That message is too long to send.",
+            "not_registered" => "You need to pick a username before chatting.",
+            "unknown_thread" => "The message you replied to is no longer available.",
+            _ => {
+                return match &self.message {
+                    Some(message) => message.clone(),
+                    None => format!("The server rejected the request ({}).", self.code),
+                }
+            }
+        };
+        friendly.to_string()
+    }
+}
+
+/// Payload of an incoming `read` frame: `from` has read up to message `id`.
+#[derive(Deserialize)]
+pub struct ReadReceipt {
+    pub from: String,
+    pub id: String,
+}
+
+#[derive(Deserialize)]
+pub struct TypingData {
+    pub from: String,
+    pub active: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterEntry {
+    pub id: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// Whether the user has gone idle.
+    #[serde(default)]
+    pub away: bool,
+}
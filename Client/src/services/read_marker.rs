@@ -0,0 +1,15 @@
+use gloo_storage::{LocalStorage, Storage};
+
+const KEY_PREFIX: &str = "yewchat.last_read.";
+
+/// The timestamp of the newest message `username` has read in this browser,
+/// so a reload doesn't bring back messages that were already read.
+pub fn load(username: &str) -> Option<f64> {
+    LocalStorage::get(format!("{}{}", KEY_PREFIX, username)).ok()
+}
+
+pub fn save(username: &str, last_read: f64) {
+    if let Err(e) = LocalStorage::set(format!("{}{}", KEY_PREFIX, username), last_read) {
+        log::error!("failed to save the read marker: {:?}", e);
+    }
+}
@@ -0,0 +1,29 @@
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+const KEY_PREFIX: &str = "yewchat.scroll.";
+
+/// Where the message list of a room was left: the message at the top of the
+/// viewport, and how far its top edge sat below the list's. Pixel offsets
+/// alone would drift as messages come and go.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollPosition {
+    pub message_id: String,
+    pub offset: f64,
+}
+
+pub fn load(room: &str) -> Option<ScrollPosition> {
+    LocalStorage::get(format!("{}{}", KEY_PREFIX, room)).ok()
+}
+
+pub fn save(room: &str, position: &ScrollPosition) {
+    if let Err(e) = LocalStorage::set(format!("{}{}", KEY_PREFIX, room), position) {
+        log::error!("failed to save the scroll position: {:?}", e);
+    }
+}
+
+/// Forgets the position, so the room opens at the newest message.
+pub fn clear(room: &str) {
+    LocalStorage::delete(format!("{}{}", KEY_PREFIX, room));
+}
@@ -0,0 +1,167 @@
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+use yew::functional::{Reducible, UseReducerHandle};
+
+use crate::utils::time::TimeFormat;
+
+const SETTINGS_KEY: &str = "yewchat.settings";
+
+/// Which key combination in the composer sends the message. The other
+/// combination inserts a newline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SendKey {
+    Enter,
+    CtrlEnter,
+}
+
+/// How much room inline images take in the message list. Clicking one
+/// always opens it full size.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImageSize {
+    Small,
+    Medium,
+    Large,
+}
+
+/// The outline of avatars, everywhere they appear.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AvatarShape {
+    Circle,
+    Rounded,
+    Square,
+}
+
+impl AvatarShape {
+    pub fn class(self) -> &'static str {
+        match self {
+            AvatarShape::Circle => "rounded-full",
+            AvatarShape::Rounded => "rounded-lg",
+            AvatarShape::Square => "rounded-none",
+        }
+    }
+
+    /// Where a status dot sits on the avatar's corner. A circle leaves the
+    /// corner empty, so its dot can sit inside the box; other shapes push
+    /// it out so it doesn't cover the picture.
+    pub fn status_dot_class(self) -> &'static str {
+        match self {
+            AvatarShape::Circle => "bottom-0 right-0",
+            AvatarShape::Rounded => "-bottom-0.5 -right-0.5",
+            AvatarShape::Square => "-bottom-1 -right-1",
+        }
+    }
+}
+
+/// The sound new messages make while the chat is in the background.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NotificationSound {
+    None,
+    Chime,
+    Pop,
+    Ding,
+}
+
+impl NotificationSound {
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationSound::None => "None",
+            NotificationSound::Chime => "Chime",
+            NotificationSound::Pop => "Pop",
+            NotificationSound::Ding => "Ding",
+        }
+    }
+
+    /// The bundled audio file, relative to the page.
+    pub fn file(self) -> Option<&'static str> {
+        match self {
+            NotificationSound::None => None,
+            NotificationSound::Chime => Some("sounds/chime.wav"),
+            NotificationSound::Pop => Some("sounds/pop.wav"),
+            NotificationSound::Ding => Some("sounds/ding.wav"),
+        }
+    }
+}
+
+/// When message timestamps are shown. Hovering one always shows the full
+/// date and time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimestampDisplay {
+    Always,
+    /// Only while the pointer or focus is on the message.
+    Hover,
+    Hidden,
+}
+
+/// User preferences, persisted to localStorage as a single JSON object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub time_format: TimeFormat,
+    pub timestamps: TimestampDisplay,
+    /// Custom avatar image shown to others instead of the generated one.
+    pub avatar_url: Option<String>,
+    pub send_key: SendKey,
+    /// Hide, rather than dim, messages from users who have left the room.
+    pub hide_departed: bool,
+    pub image_size: ImageSize,
+    /// Show the round-trip time to the server in the header.
+    pub show_latency: bool,
+    /// Use `Theme::high_contrast` in place of the embedding app's theme.
+    pub high_contrast: bool,
+    /// Show avatar images. Off, avatars are initials and no image is loaded.
+    pub show_avatars: bool,
+    pub avatar_shape: AvatarShape,
+    /// Mask the words in `filtered_words` in message text.
+    pub filter_words: bool,
+    pub filtered_words: Vec<String>,
+    pub notification_sound: NotificationSound,
+    /// Silence `notification_sound` without losing the choice.
+    pub mute_sounds: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            time_format: TimeFormat::from_locale(),
+            timestamps: TimestampDisplay::Always,
+            avatar_url: None,
+            send_key: SendKey::Enter,
+            hide_departed: false,
+            image_size: ImageSize::Medium,
+            show_latency: false,
+            high_contrast: false,
+            show_avatars: true,
+            avatar_shape: AvatarShape::Circle,
+            filter_words: false,
+            filtered_words: Vec::new(),
+            notification_sound: NotificationSound::Chime,
+            mute_sounds: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        LocalStorage::get(SETTINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = LocalStorage::set(SETTINGS_KEY, self) {
+            log::error!("failed to save settings: {:?}", e);
+        }
+    }
+}
+
+// The action is the full replacement value; components clone, edit a field
+// and dispatch it back.
+impl Reducible for Settings {
+    type Action = Settings;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        action.save();
+        Rc::new(action)
+    }
+}
+
+pub type SettingsContext = UseReducerHandle<Settings>;
@@ -0,0 +1,22 @@
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::HtmlAudioElement;
+
+use crate::services::settings::NotificationSound;
+
+/// Plays `sound`, if it has a file. Browsers refuse to play audio until the
+/// user has interacted with the page; that is logged and otherwise ignored.
+pub fn play(sound: NotificationSound) {
+    let file = match sound.file() {
+        Some(file) => file,
+        None => return,
+    };
+    let promise = HtmlAudioElement::new_with_src(file).and_then(|audio| audio.play());
+    match promise {
+        Ok(promise) => spawn_local(async move {
+            if let Err(e) = JsFuture::from(promise).await {
+                debug_log!("notification sound didn't play: {:?}", e);
+            }
+        }),
+        Err(e) => log::error!("failed to play {}: {:?}", file, e),
+    }
+}
@@ -0,0 +1,65 @@
+/// Colours for the chat screen, applied as CSS custom properties on its root
+/// element. Any CSS colour value works. `Theme::default()` is the stock look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Send button, focus rings and other accents.
+    pub primary: String,
+    pub primary_hover: String,
+    /// Behind the message list, header and typing indicator.
+    pub background: String,
+    /// The user list and the composer bar.
+    pub surface: String,
+    /// The composer's text field.
+    pub input: String,
+    pub bubble: String,
+    pub bubble_text: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            primary: "#2563eb".into(),
+            primary_hover: "#1d4ed8".into(),
+            background: "#f9fafb".into(),
+            surface: "#ffffff".into(),
+            input: "#f3f4f6".into(),
+            bubble: "#ffffff".into(),
+            bubble_text: "#1f2937".into(),
+        }
+    }
+}
+
+impl Theme {
+    /// Black on white with a dark accent for low-vision users. The chat also
+    /// darkens its grey text and borders and thickens focus outlines while
+    /// this is in use.
+    pub fn high_contrast() -> Self {
+        Self {
+            primary: "#1e3a8a".into(),
+            primary_hover: "#172554".into(),
+            background: "#ffffff".into(),
+            surface: "#ffffff".into(),
+            input: "#ffffff".into(),
+            bubble: "#ffffff".into(),
+            bubble_text: "#000000".into(),
+        }
+    }
+
+    /// The theme as a `style` attribute value.
+    pub fn css_variables(&self) -> String {
+        [
+            ("--yc-primary", &self.primary),
+            ("--yc-primary-hover", &self.primary_hover),
+            ("--yc-background", &self.background),
+            ("--yc-surface", &self.surface),
+            ("--yc-input", &self.input),
+            ("--yc-bubble", &self.bubble),
+            ("--yc-bubble-text", &self.bubble_text),
+        ]
+        .iter()
+        // A stray `;` would let one value spill into the next property.
+        .map(|(name, value)| format!("{}: {};", name, value.replace(';', "")))
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+}
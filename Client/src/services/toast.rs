@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+use yew_agent::{Agent, AgentLink, Context, Dispatched, HandlerId};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A short-lived notification shown in the corner of the screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub text: String,
+    pub severity: Severity,
+    pub duration_ms: u32,
+}
+
+impl Toast {
+    /// Errors stay up longer than other toasts, since they usually need reading.
+    pub fn new(text: impl Into<String>, severity: Severity) -> Self {
+        let duration_ms = match severity {
+            Severity::Error => 6_000,
+            _ => 3_000,
+        };
+        Self {
+            text: text.into(),
+            severity,
+            duration_ms,
+        }
+    }
+
+    pub fn with_duration(self, duration_ms: u32) -> Self {
+        Self {
+            duration_ms,
+            ..self
+        }
+    }
+}
+
+/// Shows `text` as a toast. Callable from anywhere; the `ToastStack` mounted
+/// by `Main` renders it.
+pub fn notify(text: impl Into<String>, severity: Severity) {
+    show(Toast::new(text, severity));
+}
+
+pub fn show(toast: Toast) {
+    ToastBus::dispatcher().send(toast);
+}
+
+/// Relays toasts from `notify` to every mounted toast stack.
+pub struct ToastBus {
+    link: AgentLink<ToastBus>,
+    subscribers: HashSet<HandlerId>,
+}
+
+impl Agent for ToastBus {
+    type Reach = Context<Self>;
+    type Message = ();
+    type Input = Toast;
+    type Output = Toast;
+
+    fn create(link: AgentLink<Self>) -> Self {
+        Self {
+            link,
+            subscribers: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, _msg: Self::Message) {}
+
+    fn handle_input(&mut self, toast: Self::Input, _id: HandlerId) {
+        for sub in self.subscribers.iter() {
+            self.link.respond(*sub, toast.clone())
+        }
+    }
+
+    fn connected(&mut self, id: HandlerId) {
+        self.subscribers.insert(id);
+    }
+
+    fn disconnected(&mut self, id: HandlerId) {
+        self.subscribers.remove(&id);
+    }
+}
@@ -1,52 +1,423 @@
-use futures::{channel::mpsc::Sender, SinkExt, StreamExt};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures::channel::mpsc::{Receiver, Sender};
+use futures::{future, FutureExt, SinkExt, StreamExt};
+use gloo_events::EventListener;
+use gloo_timers::future::TimeoutFuture;
 use reqwasm::websocket::{futures::WebSocket, Message};
+use crate::services::codec::Payload;
 use crate::services::event_bus::{EventBus, Request};
-use yew_agent::Dispatched;
+#[cfg(feature = "debug-overlay")]
+use crate::services::frame_log::{self, Direction};
+use crate::services::metrics;
+use crate::services::toast::{self, Severity};
+use yew::Callback;
+use yew_agent::{Dispatched, Dispatcher};
 
 use wasm_bindgen_futures::spawn_local;
 
+const WS_URL: &str = "ws://127.0.0.1:8080";
+const RECONNECT_BASE_MS: u32 = 1_000;
+const RECONNECT_MAX_MS: u32 = 30_000;
+/// Failed reconnects in a row before giving up, so a server that is gone for
+/// good doesn't keep the page retrying in the background.
+pub const DEFAULT_MAX_RECONNECTS: u32 = 10;
+/// How far either way each reconnect delay is randomly stretched, as a
+/// fraction of it, so clients dropped by the same server restart don't all
+/// come back at the same moment.
+pub const DEFAULT_RECONNECT_JITTER: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Open,
+    /// The socket dropped; attempt number `attempt` starts after `delay_ms`.
+    Reconnecting { attempt: u32, delay_ms: u32 },
+    /// Too many reconnects failed; nothing happens until `reconnect_now`.
+    GaveUp,
+    Closed,
+}
+
+/// Why a frame never made it into the outgoing queue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsError {
+    /// The frame or its payload couldn't be turned into text.
+    Serialize(String),
+    /// The queue is full or the service has shut down.
+    Queue(String),
+}
+
+impl fmt::Display for WsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WsError::Serialize(e) => write!(f, "couldn't encode the message: {}", e),
+            WsError::Queue(e) => write!(f, "couldn't queue the message: {}", e),
+        }
+    }
+}
+
+/// `strategy`'s wait in milliseconds before reconnect attempt `attempt`, or
+/// `None` once more than `max_attempts` have failed. `None` for
+/// `max_attempts` retries forever.
+pub fn retry_delay(
+    strategy: &dyn ReconnectStrategy,
+    attempt: u32,
+    max_attempts: Option<u32>,
+) -> Option<u32> {
+    match max_attempts {
+        Some(max) if attempt > max => None,
+        _ => Some(as_millis(strategy.next_delay(attempt))),
+    }
+}
+
+fn as_millis(delay: Duration) -> u32 {
+    delay.as_millis().min(u32::MAX as u128) as u32
+}
+
+/// `delay_ms` moved by up to `fraction` of itself either way, with `unit`
+/// in `[0, 1)` picking where: 0 is the shortest delay, just under 1 the
+/// longest. `fraction` is clamped to `[0, 1]`.
+pub fn jittered(delay_ms: u32, fraction: f64, unit: f64) -> u32 {
+    let fraction = fraction.clamp(0.0, 1.0);
+    (delay_ms as f64 * (1.0 + fraction * (2.0 * unit - 1.0))).round() as u32
+}
+
+/// A small xorshift generator for reconnect jitter, which `Chat` seeds
+/// from `Math.random`. Taking a seed keeps it reproducible in tests.
+pub struct Jitter(u64);
+
+impl Jitter {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero.
+        Self(seed.max(1))
+    }
+
+    /// The next number in `[0, 1)`.
+    pub fn next_unit(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// How long to wait before each reconnect attempt. `Chat` uses
+/// `ExponentialBackoff` with `Jittered` unless given another strategy.
+pub trait ReconnectStrategy {
+    /// The wait before reconnect attempt `attempt`, counting from 1.
+    fn next_delay(&self, attempt: u32) -> Duration;
+}
+
+// Strategies can't be compared, so props only see a change when a different
+// one is passed in.
+impl PartialEq for dyn ReconnectStrategy {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+/// The same wait before every attempt.
+pub struct FixedDelay(pub Duration);
+
+impl ReconnectStrategy for FixedDelay {
+    fn next_delay(&self, _attempt: u32) -> Duration {
+        self.0
+    }
+}
+
+/// A wait that starts at `base` and doubles with each attempt, up to `max`.
+pub struct ExponentialBackoff {
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(RECONNECT_BASE_MS.into()),
+            max: Duration::from_millis(RECONNECT_MAX_MS.into()),
+        }
+    }
+}
+
+impl ReconnectStrategy for ExponentialBackoff {
+    fn next_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.base.saturating_mul(1 << exponent).min(self.max)
+    }
+}
+
+/// Another strategy's delays, each moved by up to `fraction` of itself
+/// either way; see `jittered`.
+pub struct Jittered<S> {
+    inner: S,
+    fraction: f64,
+    rng: RefCell<Jitter>,
+}
+
+impl<S: ReconnectStrategy> Jittered<S> {
+    pub fn new(inner: S, fraction: f64, seed: u64) -> Self {
+        Self {
+            inner,
+            fraction,
+            rng: RefCell::new(Jitter::new(seed)),
+        }
+    }
+}
+
+impl<S: ReconnectStrategy> ReconnectStrategy for Jittered<S> {
+    fn next_delay(&self, attempt: u32) -> Duration {
+        let delay_ms = as_millis(self.inner.next_delay(attempt));
+        let unit = self.rng.borrow_mut().next_unit();
+        Duration::from_millis(jittered(delay_ms, self.fraction, unit).into())
+    }
+}
+
+/// Browsers block `ws://` sockets from pages served over https, except to
+/// loopback addresses, without telling the page why. Returns the `wss://`
+/// URL to try instead when `url` would be blocked on a page served with
+/// `page_protocol` (as in `location.protocol`, e.g. `"https:"`).
+pub fn upgrade_for_page(url: &str, page_protocol: &str) -> Option<String> {
+    let rest = url.strip_prefix("ws://")?;
+    if page_protocol != "https:" {
+        return None;
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(v6),
+        None => authority.split(':').next().unwrap_or(authority),
+    };
+    let loopback = host.eq_ignore_ascii_case("localhost")
+        || host.to_ascii_lowercase().ends_with(".localhost")
+        || host.starts_with("127.")
+        || host == "::1";
+    (!loopback).then(|| format!("wss://{}", rest))
+}
+
+// The server URL, upgraded to wss:// if the page's protocol requires it.
+fn server_url() -> String {
+    let protocol = gloo_utils::window().location().protocol().unwrap_or_default();
+    match upgrade_for_page(WS_URL, &protocol) {
+        Some(upgraded) => {
+            log::error!(
+                "{} would be blocked as mixed content on an https page; trying {}",
+                WS_URL,
+                upgraded
+            );
+            toast::notify(
+                format!(
+                    "This page is served over HTTPS, so the browser blocks the insecure chat \
+                     server address {}. Trying {} instead; the server needs TLS for that to work.",
+                    WS_URL, upgraded
+                ),
+                Severity::Warning,
+            );
+            upgraded
+        }
+        None => WS_URL.to_string(),
+    }
+}
+
 pub struct WebsocketService {
-    pub tx: Sender<String>,
+    pub tx: Sender<Payload>,
+    // `true` also forgets the failed attempts so far.
+    reconnect_tx: Sender<bool>,
+    // Dropping these with the service removes them from the page.
+    _wake_listeners: Vec<EventListener>,
 }
 
-impl WebsocketService {
-    pub fn new() -> Self {
-        let ws = WebSocket::open("ws://127.0.0.1:8080").unwrap();
+// Whether a page that just woke up should reconnect now instead of waiting
+// out the backoff.
+fn wakes(state: ConnectionState) -> bool {
+    matches!(state, ConnectionState::Reconnecting { .. } | ConnectionState::GaveUp)
+}
 
-        let (mut write, mut read) = ws.split();
+// How a single connection ended.
+enum Outcome {
+    // The socket closed or failed; `opened` tells whether it ever connected.
+    Lost { opened: bool },
+    // Every sender was dropped, so nobody is using the service any more.
+    Shutdown,
+}
 
-        let (in_tx, mut in_rx) = futures::channel::mpsc::channel::<String>(1000);
+impl WebsocketService {
+    /// With `reconnect_on_wake`, the page coming back online or becoming
+    /// visible again while disconnected reconnects at once with a fresh
+    /// backoff, so the chat recovers promptly after sleep or a network blip.
+    /// `strategy` picks the wait before each reconnect.
+    pub fn new(
+        on_state: Callback<ConnectionState>,
+        max_reconnects: Option<u32>,
+        reconnect_on_wake: bool,
+        strategy: Rc<dyn ReconnectStrategy>,
+    ) -> Self {
+        let (in_tx, mut in_rx) = futures::channel::mpsc::channel::<Payload>(1000);
+        let (reconnect_tx, mut reconnect_rx) = futures::channel::mpsc::channel::<bool>(1);
         let mut event_bus = EventBus::dispatcher();
+        let current = Rc::new(Cell::new(ConnectionState::Connecting));
 
-        spawn_local(async move {
-            while let Some(s) = in_rx.next().await {
-                log::debug!("got event from channel! {}", s);
-                write.send(Message::Text(s)).await.unwrap();
-            }
-        });
+        let wake_listeners = if reconnect_on_wake {
+            let wake = {
+                let current = current.clone();
+                let reconnect_tx = reconnect_tx.clone();
+                move || {
+                    if wakes(current.get()) {
+                        debug_log!("page woke up; reconnecting now");
+                        let _ = reconnect_tx.clone().try_send(true);
+                    }
+                }
+            };
+            let on_online = wake.clone();
+            vec![
+                EventListener::new(&gloo_utils::window(), "online", move |_| on_online()),
+                EventListener::new(&gloo_utils::document(), "visibilitychange", move |_| {
+                    if !gloo_utils::document().hidden() {
+                        wake();
+                    }
+                }),
+            ]
+        } else {
+            Vec::new()
+        };
 
+        let set_state = move |state: ConnectionState| {
+            current.set(state);
+            metrics::record(|m| m.connection = state);
+            on_state.emit(state);
+        };
+
+        // Frames sent while disconnected wait in the channel and go out once
+        // the next connection opens.
         spawn_local(async move {
-            while let Some(msg) = read.next().await {
-                match msg {
-                    Ok(Message::Text(data)) => {
-                        log::debug!("from websocket: {}", data);
-                        event_bus.send(Request::EventBusMsg(data));
+            // Not before this task runs, so the toast stack is mounted to show
+            // any warning.
+            let url = server_url();
+            let mut attempt = 0;
+            loop {
+                set_state(ConnectionState::Connecting);
+                match run_connection(&url, &mut in_rx, &mut event_bus, &set_state).await {
+                    Outcome::Shutdown => break,
+                    Outcome::Lost { opened } => {
+                        if opened {
+                            attempt = 0;
+                        }
                     }
-                    Ok(Message::Bytes(b)) => {
-                        let decoded = std::str::from_utf8(&b);
-                        if let Ok(val) = decoded {
-                            log::debug!("from websocket: {}", val);
-                            event_bus.send(Request::EventBusMsg(val.into()));
+                }
+                attempt += 1;
+                let delay_ms = match retry_delay(&*strategy, attempt, max_reconnects) {
+                    Some(delay_ms) => delay_ms,
+                    None => {
+                        set_state(ConnectionState::GaveUp);
+                        // A manual reconnect starts counting from scratch.
+                        if reconnect_rx.next().await.is_none() {
+                            break;
                         }
+                        attempt = 0;
+                        continue;
                     }
-                    Err(e) => {
-                        log::error!("ws: {:?}", e)
+                };
+                set_state(ConnectionState::Reconnecting { attempt, delay_ms });
+                futures::select! {
+                    _ = TimeoutFuture::new(delay_ms).fuse() => {}
+                    reset = reconnect_rx.next() => {
+                        if reset == Some(true) {
+                            attempt = 0;
+                        }
                     }
                 }
+                metrics::record(|m| m.reconnects += 1);
             }
-            log::debug!("WebSocket Closed");
+            debug_log!("WebSocket Closed");
+            set_state(ConnectionState::Closed);
         });
 
-        Self { tx: in_tx }
+        Self {
+            tx: in_tx,
+            reconnect_tx,
+            _wake_listeners: wake_listeners,
+        }
+    }
+
+    /// Skips the remaining backoff delay and reconnects right away, or starts
+    /// over after the service gave up.
+    pub fn reconnect_now(&self) {
+        let _ = self.reconnect_tx.clone().try_send(false);
+    }
+}
+
+async fn run_connection(
+    url: &str,
+    in_rx: &mut Receiver<Payload>,
+    event_bus: &mut Dispatcher<EventBus>,
+    set_state: &impl Fn(ConnectionState),
+) -> Outcome {
+    let ws = match WebSocket::open(url) {
+        Ok(ws) => ws,
+        Err(e) => {
+            log::error!("ws: {:?}", e);
+            metrics::record(|m| m.errors += 1);
+            return Outcome::Lost { opened: false };
+        }
+    };
+    let (mut write, read) = ws.split();
+    let mut read = read.fuse();
+
+    // The sink only becomes ready once the socket opens. If it fails first,
+    // the read half reports the error and then ends.
+    let opened = futures::select! {
+        ready = future::poll_fn(|cx| write.poll_ready_unpin(cx)).fuse() => ready.is_ok(),
+        _ = read.next() => false,
+    };
+    if !opened {
+        metrics::record(|m| m.errors += 1);
+        return Outcome::Lost { opened: false };
+    }
+    set_state(ConnectionState::Open);
+
+    loop {
+        futures::select! {
+            outgoing = in_rx.next() => match outgoing {
+                Some(payload) => {
+                    debug_log!("got event from channel! {:?}", payload);
+                    #[cfg(feature = "debug-overlay")]
+                    frame_log::record(Direction::Outgoing, &payload);
+                    let message = match payload {
+                        Payload::Text(s) => Message::Text(s),
+                        Payload::Binary(b) => Message::Bytes(b),
+                    };
+                    if let Err(e) = write.send(message).await {
+                        log::error!("ws: {:?}", e);
+                        metrics::record(|m| m.errors += 1);
+                        return Outcome::Lost { opened: true };
+                    }
+                    metrics::record(|m| m.messages_sent += 1);
+                }
+                None => return Outcome::Shutdown,
+            },
+            incoming = read.next() => match incoming {
+                Some(Ok(message)) => {
+                    // reqwasm opens sockets with binaryType "arraybuffer", so
+                    // binary frames arrive as bytes; the codec decides what
+                    // they mean.
+                    let payload = match message {
+                        Message::Text(s) => Payload::Text(s),
+                        Message::Bytes(b) => Payload::Binary(b),
+                    };
+                    debug_log!("from websocket: {:?}", payload);
+                    #[cfg(feature = "debug-overlay")]
+                    frame_log::record(Direction::Incoming, &payload);
+                    metrics::record(|m| m.messages_received += 1);
+                    event_bus.send(Request::EventBusMsg(payload));
+                }
+                Some(Err(e)) => {
+                    log::error!("ws: {:?}", e);
+                    metrics::record(|m| m.errors += 1);
+                }
+                None => return Outcome::Lost { opened: true },
+            },
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,698 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::de::DeserializeOwned;
+
+use crate::services::protocol::{
+    Category, HistoryPage, MessageData, MessageEdit, MsgTypes, ReadReceipt, RosterEntry, TypingData,
+    WebSocketMessage,
+};
+use crate::utils::dicebear::DicebearVersion;
+use crate::utils::fuzzy;
+use crate::utils::time::{normalize_timestamp, now_ms};
+
+#[derive(Clone)]
+pub struct UserProfile {
+    pub id: String,
+    pub name: String,
+    pub avatar: String,
+    pub away: bool,
+    // Whether `avatar` came from the user rather than being generated.
+    custom_avatar: bool,
+}
+
+impl UserProfile {
+    /// Users without a custom avatar get one generated from `avatar_api`.
+    pub fn new(entry: RosterEntry, avatar_api: DicebearVersion) -> Self {
+        let custom_avatar = entry.avatar.is_some();
+        let avatar = entry
+            .avatar
+            .unwrap_or_else(|| avatar_api.avatar_url(&entry.id));
+        Self {
+            id: entry.id,
+            name: entry.display_name,
+            avatar,
+            away: entry.away,
+            custom_avatar,
+        }
+    }
+
+    fn has_avatar_of(&self, entry: &RosterEntry) -> bool {
+        match &entry.avatar {
+            Some(avatar) => self.custom_avatar && avatar == &self.avatar,
+            None => !self.custom_avatar,
+        }
+    }
+}
+
+/// Identical messages from the same sender this close together are shown as
+/// one, with a count.
+pub const DEFAULT_DUPLICATE_WINDOW_MS: u32 = 5_000;
+
+/// Where a roster entry is in its join or leave transition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Presence {
+    Present,
+    Joined,
+    Leaving,
+}
+
+/// Users and messages for a `ChatState` to start with instead of an empty
+/// room, for tests, demos and pre-rendering without a server.
+#[derive(Clone, Default)]
+pub struct Seed {
+    pub users: Vec<RosterEntry>,
+    /// Oldest first, as in a history page; replies go to their threads.
+    pub messages: Vec<MessageData>,
+    /// Whether the server has older messages to page back through.
+    pub more: bool,
+}
+
+/// Everything the chat screen knows about the room, independent of the DOM
+/// and the socket. `Chat` feeds incoming frames to `apply` and renders from it.
+pub struct ChatState {
+    pub username: String,
+    pub users: Vec<UserProfile>,
+    pub messages: Vec<MessageData>,
+    pub typing_users: BTreeSet<String>,
+    // Users who appeared in the latest roster update, and users who dropped
+    // out of recent ones with the position they had, so the view can animate
+    // them in and out. Cleared by `settle_presence`.
+    pub joined: HashSet<String>,
+    pub leaving: Vec<(usize, UserProfile)>,
+    // Pinned messages, oldest pin first. These are copies, so pins older
+    // than the loaded history still show.
+    pub pinned: Vec<MessageData>,
+    // Set once the server reports there is no older history to page in.
+    pub history_complete: bool,
+    // While set, incoming messages from others arrive unread.
+    pub away: bool,
+    // Timestamp of the newest message that has been read. History newer than
+    // this comes in unread, so it is persisted across reloads.
+    pub last_read: Option<f64>,
+    // `None` never collapses repeated messages.
+    pub duplicate_window_ms: Option<u32>,
+    pub avatar_api: DicebearVersion,
+    // Replies by the id of the message they reply to, oldest first. A thread
+    // may be loaded before its root is.
+    pub threads: HashMap<String, Vec<MessageData>>,
+    /// User id -> the newest message they have read, from their receipts.
+    pub read_receipts: HashMap<String, String>,
+    // The thread the latest message went to, or `None` for the message list.
+    latest_thread: Option<String>,
+}
+
+impl ChatState {
+    pub fn new(username: String) -> Self {
+        Self {
+            username,
+            users: vec![],
+            messages: vec![],
+            typing_users: BTreeSet::new(),
+            joined: HashSet::new(),
+            leaving: vec![],
+            pinned: vec![],
+            history_complete: false,
+            away: false,
+            last_read: None,
+            duplicate_window_ms: Some(DEFAULT_DUPLICATE_WINDOW_MS),
+            avatar_api: DicebearVersion::default(),
+            threads: HashMap::new(),
+            read_receipts: HashMap::new(),
+            latest_thread: None,
+        }
+    }
+
+    /// Applies an incoming frame and returns whether the view needs to re-render.
+    pub fn apply(&mut self, msg: WebSocketMessage) -> bool {
+        match msg.message_type.category() {
+            Category::Silent => self.apply_control(msg),
+            category => self.push_entry(msg, category).is_some(),
+        }
+    }
+
+    /// Like `apply`, but returns the entry a `message`, `notice` or
+    /// `announcement` frame added, with its own id and client id even when
+    /// it was collapsed into an earlier copy. `None` when nothing was added.
+    pub fn apply_entry(&mut self, msg: WebSocketMessage) -> Option<MessageData> {
+        match msg.message_type.category() {
+            Category::Silent => {
+                self.apply_control(msg);
+                None
+            }
+            category => self.push_entry(msg, category),
+        }
+    }
+
+    // Adds a frame that renders in the message list.
+    fn push_entry(&mut self, msg: WebSocketMessage, category: Category) -> Option<MessageData> {
+        let mut entry: MessageData = match msg.message_type {
+            // Notices and announcements are plain text with no sender.
+            MsgTypes::Notice | MsgTypes::Announcement => MessageData {
+                message: msg.data.unwrap_or_default(),
+                ..Default::default()
+            },
+            _ => payload(&msg)?,
+        };
+        // A resent message the server already had comes back with its id.
+        if matches!(&entry.id, Some(id) if self.has_message(id)) {
+            return None;
+        }
+        entry.time = entry.time.and_then(|time| normalize_timestamp(time, now_ms()));
+        entry.category = category;
+        self.latest_thread = entry.thread_id.clone();
+        if let Some(root) = entry.thread_id.clone() {
+            // Threads have no unread tracking of their own.
+            self.advance_marker(entry.time);
+            self.typing_users.remove(&entry.from);
+            self.threads.entry(root).or_default().push(entry.clone());
+            return Some(entry);
+        }
+        entry.unread = self.away && category == Category::Chat && entry.from != self.username;
+        if !entry.unread {
+            self.advance_marker(entry.time);
+        }
+        self.typing_users.remove(&entry.from);
+        if let (Some(id), Some(original)) = (&entry.id, self.duplicate_of(&entry)) {
+            original.duplicates.push(id.clone());
+            return Some(entry);
+        }
+        self.messages.push(entry.clone());
+        Some(entry)
+    }
+
+    // The message `entry` repeats: the latest message, from the same user with
+    // the same text, sent at most the duplicate window before it.
+    fn duplicate_of(&mut self, entry: &MessageData) -> Option<&mut MessageData> {
+        let window = self.duplicate_window_ms?;
+        let last = self.messages.last_mut()?;
+        let within = matches!((last.time, entry.time), (Some(a), Some(b)) if b - a <= window as f64);
+        (entry.category == Category::Chat
+            && last.category == Category::Chat
+            && !last.deleted
+            && last.from == entry.from
+            && last.message == entry.message
+            && last.attachments == entry.attachments
+            && within)
+            .then_some(last)
+    }
+
+    // Handles frames that only update state and are not shown themselves.
+    fn apply_control(&mut self, msg: WebSocketMessage) -> bool {
+        match msg.message_type {
+            MsgTypes::Users => {
+                // Older servers only send the list of ids.
+                let users_from_message = match msg.data {
+                    Some(_) => match payload(&msg) {
+                        Some(entries) => entries,
+                        None => return false,
+                    },
+                    None => msg
+                        .data_array
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|id| RosterEntry {
+                            display_name: id.clone(),
+                            id,
+                            avatar: None,
+                            away: false,
+                        })
+                        .collect(),
+                };
+                if !self.update_roster(users_from_message) {
+                    return false;
+                }
+                let users = &self.users;
+                self.typing_users
+                    .retain(|id| users.iter().any(|u| &u.id == id));
+                true
+            }
+            MsgTypes::Typing => {
+                let typing: TypingData = match payload(&msg) {
+                    Some(typing) => typing,
+                    None => return false,
+                };
+                if typing.from == self.username {
+                    return false;
+                }
+                if typing.active {
+                    self.typing_users.insert(typing.from)
+                } else {
+                    self.typing_users.remove(&typing.from)
+                }
+            }
+            MsgTypes::Read => {
+                let receipt: ReadReceipt = match payload(&msg) {
+                    Some(receipt) => receipt,
+                    None => return false,
+                };
+                if receipt.from == self.username {
+                    return false;
+                }
+                self.read_receipts.insert(receipt.from, receipt.id.clone()).as_ref() != Some(&receipt.id)
+            }
+            MsgTypes::Edit => {
+                let edit: MessageEdit = match payload(&msg) {
+                    Some(edit) => edit,
+                    None => return false,
+                };
+                self.uncollapse(&edit.id);
+                // A pinned message has its copy in `pinned` updated too.
+                let mut changed = false;
+                let replies = self.threads.values_mut().flatten();
+                for message in self.messages.iter_mut().chain(self.pinned.iter_mut()).chain(replies) {
+                    if message.id.as_deref() == Some(edit.id.as_str()) {
+                        message.message = edit.message.clone();
+                        message.edited = true;
+                        changed = true;
+                    }
+                }
+                changed
+            }
+            MsgTypes::Delete => {
+                let id = match msg.data {
+                    Some(id) => id,
+                    None => return false,
+                };
+                self.uncollapse(&id);
+                let unpinned = self.unpin(&id);
+                match self.message_mut(&id) {
+                    Some(message) => {
+                        message.message.clear();
+                        message.deleted = true;
+                        true
+                    }
+                    None => unpinned,
+                }
+            }
+            MsgTypes::Pin => {
+                let message: MessageData = match payload(&msg) {
+                    Some(message) => message,
+                    None => return false,
+                };
+                match &message.id {
+                    Some(id) if !self.is_pinned(id) => {
+                        self.pinned.push(message);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            MsgTypes::Unpin => match msg.data {
+                Some(id) => self.unpin(&id),
+                None => false,
+            },
+            MsgTypes::PinOrder => self.reorder_pins(&msg.data_array.unwrap_or_default()),
+            MsgTypes::History => {
+                let page: HistoryPage = match payload(&msg) {
+                    Some(page) => page,
+                    None => return false,
+                };
+                self.prepend_history(page.messages, page.more);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Starts from `seed`, as if its roster and a history page had just
+    /// arrived, so live frames apply on top of it as usual.
+    pub fn seed(&mut self, seed: Seed) {
+        self.update_roster(seed.users);
+        self.prepend_history(seed.messages, seed.more);
+    }
+
+    // Puts a page of older messages above the ones loaded, and its replies
+    // into their threads.
+    fn prepend_history(&mut self, messages: Vec<MessageData>, more: bool) {
+        self.history_complete = !more;
+        // Messages broadcast while the request was in flight may also
+        // be part of the page.
+        let now = now_ms();
+        let older = messages
+            .into_iter()
+            .filter(|m| m.id.as_deref().is_none_or(|id| self.message(id).is_none()))
+            .map(|mut m| {
+                m.time = m.time.and_then(|time| normalize_timestamp(time, now));
+                m.unread = m.from != self.username
+                    && matches!((m.time, self.last_read), (Some(t), Some(read)) if t > read);
+                m
+            })
+            .collect::<Vec<_>>();
+        for m in older.iter().filter(|m| !m.unread) {
+            self.advance_marker(m.time);
+        }
+        let (replies, older): (Vec<_>, Vec<_>) = older.into_iter().partition(|m| m.thread_id.is_some());
+        let mut older_replies: HashMap<String, Vec<MessageData>> = HashMap::new();
+        for mut reply in replies {
+            reply.unread = false;
+            let root = reply.thread_id.clone().unwrap_or_default();
+            older_replies.entry(root).or_default().push(reply);
+        }
+        for (root, replies) in older_replies {
+            self.threads.entry(root).or_default().splice(0..0, replies);
+        }
+        self.messages.splice(0..0, older);
+    }
+
+    // Reuses the existing profile for every user still present so unchanged
+    // entries keep their avatar URL. Returns false if the roster is unchanged.
+    fn update_roster(&mut self, entries: Vec<RosterEntry>) -> bool {
+        let entries = disambiguate(entries);
+        let unchanged = self.users.len() == entries.len()
+            && self
+                .users
+                .iter()
+                .zip(&entries)
+                .all(|(u, e)| u.id == e.id && u.name == e.display_name && u.away == e.away && u.has_avatar_of(e));
+        if unchanged {
+            return false;
+        }
+        // Nobody "joins" in the first roster; it is everyone already here.
+        let first_roster = self.users.is_empty();
+        self.joined = entries
+            .iter()
+            .filter(|e| !first_roster && self.user(&e.id).is_none())
+            .map(|e| e.id.clone())
+            .collect();
+        self.leaving.retain(|(_, u)| !entries.iter().any(|e| e.id == u.id));
+        for (i, user) in self.users.iter().enumerate() {
+            if !entries.iter().any(|e| e.id == user.id) {
+                self.leaving.push((i, user.clone()));
+            }
+        }
+        let mut previous: HashMap<String, UserProfile> =
+            self.users.drain(..).map(|u| (u.id.clone(), u)).collect();
+        self.users = entries
+            .into_iter()
+            .map(|entry| match previous.remove(&entry.id) {
+                Some(profile) if profile.has_avatar_of(&entry) => UserProfile {
+                    name: entry.display_name,
+                    away: entry.away,
+                    ..profile
+                },
+                _ => UserProfile::new(entry, self.avatar_api),
+            })
+            .collect();
+        true
+    }
+
+    /// Index of the oldest unread message, where the "New messages" divider goes.
+    pub fn first_unread(&self) -> Option<usize> {
+        self.messages.iter().position(|m| m.unread)
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.messages.iter().filter(|m| m.unread).count()
+    }
+
+    /// Marks the messages up to and including `index` as read, e.g. once
+    /// they have been scrolled into view. Returns whether any were unread.
+    pub fn mark_read_through(&mut self, index: usize) -> bool {
+        let end = (index + 1).min(self.messages.len());
+        let mut changed = false;
+        let mut newest = None;
+        for message in &mut self.messages[..end] {
+            changed |= std::mem::take(&mut message.unread);
+            newest = message.time.or(newest);
+        }
+        self.advance_marker(newest);
+        changed
+    }
+
+    pub fn mark_all_read(&mut self) -> bool {
+        match self.messages.len() {
+            0 => false,
+            len => self.mark_read_through(len - 1),
+        }
+    }
+
+    fn advance_marker(&mut self, time: Option<f64>) {
+        if let Some(time) = time {
+            if self.last_read.is_none_or(|read| time > read) {
+                self.last_read = Some(time);
+            }
+        }
+    }
+
+    /// Removes the messages whose time is up, everywhere they show: the
+    /// list, the pins and the threads, along with the threads of expired
+    /// roots. Returns whether anything was removed.
+    pub fn expire(&mut self, now: f64) -> bool {
+        let live = |m: &MessageData| m.expires_at.is_none_or(|at| at > now);
+        let count = |s: &Self| s.messages.len() + s.pinned.len() + s.threads.values().map(Vec::len).sum::<usize>();
+        let before = count(self);
+        let roots: Vec<String> = self
+            .messages
+            .iter()
+            .filter(|m| !live(m))
+            .filter_map(|m| m.id.clone())
+            .collect();
+        for root in &roots {
+            self.threads.remove(root);
+        }
+        self.messages.retain(live);
+        self.pinned.retain(live);
+        for replies in self.threads.values_mut() {
+            replies.retain(live);
+        }
+        count(self) != before
+    }
+
+    /// When the next disappearing message is due to go.
+    pub fn next_expiry(&self) -> Option<f64> {
+        self.messages
+            .iter()
+            .chain(&self.pinned)
+            .chain(self.threads.values().flatten())
+            .filter_map(|m| m.expires_at)
+            .reduce(f64::min)
+    }
+
+    /// The newest message in the list that we have read, which is what our
+    /// read receipt points at.
+    pub fn newest_read(&self) -> Option<&str> {
+        let end = self.first_unread().unwrap_or(self.messages.len());
+        self.messages[..end]
+            .iter()
+            .rev()
+            .filter(|m| m.category == Category::Chat)
+            .find_map(|m| m.id.as_deref())
+    }
+
+    /// Who has read up to where, keyed by message id: everyone else in the
+    /// room, in roster order, under the newest loaded message they have
+    /// seen. A receipt for a collapsed duplicate counts for the message it
+    /// collapsed into.
+    pub fn seen_by(&self) -> HashMap<&str, Vec<&UserProfile>> {
+        let mut seen: HashMap<&str, Vec<&UserProfile>> = HashMap::new();
+        for user in self.users.iter().filter(|u| u.id != self.username) {
+            let read = match self.read_receipts.get(&user.id) {
+                Some(read) => read,
+                None => continue,
+            };
+            let row = self
+                .messages
+                .iter()
+                .find(|m| m.id.as_ref() == Some(read) || m.duplicates.contains(read));
+            if let Some(id) = row.and_then(|m| m.id.as_deref()) {
+                seen.entry(id).or_default().push(user);
+            }
+        }
+        seen
+    }
+
+    /// The roster as shown: current users, with users who just left still in
+    /// their old places so they can fade out.
+    pub fn roster_view(&self) -> Vec<(&UserProfile, Presence)> {
+        let mut view = self
+            .users
+            .iter()
+            .map(|u| {
+                let presence = if self.joined.contains(&u.id) {
+                    Presence::Joined
+                } else {
+                    Presence::Present
+                };
+                (u, presence)
+            })
+            .collect::<Vec<_>>();
+        for (i, user) in &self.leaving {
+            view.insert((*i).min(view.len()), (user, Presence::Leaving));
+        }
+        view
+    }
+
+    /// The sidebar's roster: people matching `filter` best first, or with
+    /// no filter everyone active before anyone away. At most `limit` are
+    /// returned, along with how many more there are.
+    pub fn roster_page(&self, filter: &str, limit: Option<usize>) -> (Vec<(&UserProfile, Presence)>, usize) {
+        let mut roster = if filter.is_empty() {
+            let mut roster = self.roster_view();
+            roster.sort_by_key(|(u, _)| u.away);
+            roster
+        } else {
+            fuzzy::rank(self.roster_view(), |(u, _)| fuzzy::best_score(filter, &[&u.name, &u.id]))
+        };
+        let shown = limit.map_or(roster.len(), |limit| limit.min(roster.len()));
+        let more = roster.len() - shown;
+        roster.truncate(shown);
+        (roster, more)
+    }
+
+    /// Ends the join and leave transitions. Returns whether any were running.
+    pub fn settle_presence(&mut self) -> bool {
+        let changed = !self.joined.is_empty() || !self.leaving.is_empty();
+        self.joined.clear();
+        self.leaving.clear();
+        changed
+    }
+
+    pub fn is_pinned(&self, id: &str) -> bool {
+        self.pinned.iter().any(|m| m.id.as_deref() == Some(id))
+    }
+
+    fn unpin(&mut self, id: &str) -> bool {
+        let before = self.pinned.len();
+        self.pinned.retain(|m| m.id.as_deref() != Some(id));
+        self.pinned.len() != before
+    }
+
+    /// Moves pinned message `id` to `index` in `pinned`, shifting the ones
+    /// in between along. Returns whether the order changed.
+    pub fn move_pin(&mut self, id: &str, index: usize) -> bool {
+        let from = match self.pinned.iter().position(|m| m.id.as_deref() == Some(id)) {
+            Some(from) => from,
+            None => return false,
+        };
+        let to = index.min(self.pinned.len() - 1);
+        if from == to {
+            return false;
+        }
+        let pin = self.pinned.remove(from);
+        self.pinned.insert(to, pin);
+        true
+    }
+
+    /// The ids of the pinned messages in order, as a `pinorder` frame sends
+    /// them.
+    pub fn pin_order(&self) -> Vec<String> {
+        self.pinned.iter().filter_map(|m| m.id.clone()).collect()
+    }
+
+    // Puts the pins in the order of `ids`. Any it doesn't list, e.g. pinned
+    // while the order was on its way, keep their places after the rest.
+    fn reorder_pins(&mut self, ids: &[String]) -> bool {
+        let before = self.pin_order();
+        self.pinned.sort_by_key(|m| {
+            m.id.as_ref()
+                .and_then(|id| ids.iter().position(|i| i == id))
+                .unwrap_or(ids.len())
+        });
+        self.pin_order() != before
+    }
+
+    // Before message `id` is edited or deleted on its own: splits it back out
+    // if it was collapsed into an earlier copy, or moves the copies collapsed
+    // into it onto an entry of their own.
+    fn uncollapse(&mut self, id: &str) {
+        for i in 0..self.messages.len() {
+            let message = &mut self.messages[i];
+            let split = if message.id.as_deref() == Some(id) && !message.duplicates.is_empty() {
+                let mut ids = std::mem::take(&mut message.duplicates);
+                let first = ids.remove(0);
+                (first, ids)
+            } else if let Some(pos) = message.duplicates.iter().position(|d| d == id) {
+                (message.duplicates.remove(pos), vec![])
+            } else {
+                continue;
+            };
+            let mut copy = message.clone();
+            copy.id = Some(split.0);
+            copy.duplicates = split.1;
+            self.messages.insert(i + 1, copy);
+            return;
+        }
+    }
+
+    /// A message from the list or from any thread.
+    pub fn message(&self, id: &str) -> Option<&MessageData> {
+        self.messages
+            .iter()
+            .chain(self.threads.values().flatten())
+            .find(|m| m.id.as_deref() == Some(id))
+    }
+
+    fn message_mut(&mut self, id: &str) -> Option<&mut MessageData> {
+        self.messages
+            .iter_mut()
+            .chain(self.threads.values_mut().flatten())
+            .find(|m| m.id.as_deref() == Some(id))
+    }
+
+    // Whether message `id` is loaded, including as a collapsed duplicate.
+    fn has_message(&self, id: &str) -> bool {
+        self.message(id).is_some() || self.messages.iter().any(|m| m.duplicates.iter().any(|d| d == id))
+    }
+
+    /// The replies to message `root`, oldest first.
+    pub fn replies(&self, root: &str) -> &[MessageData] {
+        self.threads.get(root).map_or(&[], Vec::as_slice)
+    }
+
+    /// The message the latest `message` frame added, in the list or in a
+    /// thread.
+    pub fn latest(&self) -> Option<&MessageData> {
+        match &self.latest_thread {
+            Some(root) => self.replies(root).last(),
+            None => self.messages.last(),
+        }
+    }
+
+    pub fn user(&self, id: &str) -> Option<&UserProfile> {
+        self.users.iter().find(|u| u.id == id)
+    }
+
+    pub fn display_name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.user(id).map(|u| u.name.as_str()).unwrap_or(id)
+    }
+}
+
+// Ids are what messages are attributed by, so a repeated id can only be a
+// server bug: keep the first entry. Users who share a display name under
+// different ids get their id appended, e.g. "Sam (sam2)".
+fn disambiguate(entries: Vec<RosterEntry>) -> Vec<RosterEntry> {
+    let mut seen = HashSet::new();
+    let mut entries = entries
+        .into_iter()
+        .filter(|e| {
+            let first = seen.insert(e.id.clone());
+            if !first {
+                debug_log!("dropping duplicate roster entry for {:?}", e.id);
+            }
+            first
+        })
+        .collect::<Vec<_>>();
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    for entry in &entries {
+        *name_counts.entry(entry.display_name.clone()).or_default() += 1;
+    }
+    for entry in &mut entries {
+        if name_counts[&entry.display_name] > 1 && entry.display_name != entry.id {
+            entry.display_name = format!("{} ({})", entry.display_name, entry.id);
+        }
+    }
+    entries
+}
+
+// Decodes the JSON nested in a frame's `data`. Frames that don't match the
+// protocol are dropped rather than taking the chat screen down with them.
+fn payload<T: DeserializeOwned>(msg: &WebSocketMessage) -> Option<T> {
+    let data = msg.data.as_deref()?;
+    match serde_json::from_str(data) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            debug_log!("dropping malformed {:?} frame: {:?}", msg.message_type, e);
+            None
+        }
+    }
+}
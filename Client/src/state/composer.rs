@@ -0,0 +1,53 @@
+/// The message composer's contents. The text box mirrors `text` and reports
+/// every change back, so sending never has to read the DOM.
+#[derive(Debug, Default)]
+pub struct Composer {
+    pub text: String,
+    /// The id of our message being edited, if any.
+    pub editing: Option<String>,
+    /// URLs that go out with the next new message.
+    pub attachments: Vec<String>,
+}
+
+/// What a submit sends.
+#[derive(Debug, PartialEq)]
+pub enum Submission {
+    Edit {
+        id: String,
+        message: String,
+    },
+    Message {
+        text: String,
+        attachments: Vec<String>,
+    },
+}
+
+impl Composer {
+    /// Takes what to send, leaving the composer empty for the next message.
+    ///
+    /// Blank text sends nothing, unless a new message has attachments, and
+    /// ends an edit; the blank text itself stays put.
+    pub fn submit(&mut self) -> Option<Submission> {
+        let blank = self.text.trim().is_empty();
+        match self.editing.take() {
+            Some(_) if blank => None,
+            Some(id) => Some(Submission::Edit {
+                id,
+                message: std::mem::take(&mut self.text),
+            }),
+            None if blank && self.attachments.is_empty() => None,
+            None => Some(Submission::Message {
+                text: std::mem::take(&mut self.text),
+                attachments: std::mem::take(&mut self.attachments),
+            }),
+        }
+    }
+
+    /// Puts `quoted` below anything already typed.
+    pub fn append_quote(&mut self, quoted: &str) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+        self.text.push_str(quoted);
+    }
+}
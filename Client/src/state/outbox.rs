@@ -0,0 +1,116 @@
+use crate::services::protocol::WebSocketMessage;
+
+/// How many times an unconfirmed message is sent again before it counts as
+/// failed.
+pub const MAX_RESENDS: u32 = 1;
+
+struct InFlight {
+    frame: WebSocketMessage,
+    sent_at: f64,
+    resends: u32,
+}
+
+/// What `Outbox::expire` found overdue.
+#[derive(Default)]
+pub struct Expired {
+    /// Frames to send again, unchanged, so the server can drop the copy if
+    /// the first one did arrive.
+    pub resend: Vec<WebSocketMessage>,
+    /// Messages given up on after their last resend.
+    pub failed: usize,
+}
+
+/// Messages we sent and the server hasn't echoed yet, oldest first.
+pub struct Outbox {
+    in_flight: Vec<InFlight>,
+    // Keeps ids unique across reloads; the counter keeps them unique within one.
+    session: String,
+    next_id: u64,
+}
+
+impl Outbox {
+    pub fn new(session: String) -> Self {
+        Self {
+            in_flight: Vec::new(),
+            session,
+            next_id: 1,
+        }
+    }
+
+    /// Gives `frame` a client id and starts waiting for its echo. Returns
+    /// the frame to send.
+    pub fn track(&mut self, mut frame: WebSocketMessage, now: f64) -> WebSocketMessage {
+        frame.client_id = Some(format!("{}-{}", self.session, self.next_id));
+        self.next_id += 1;
+        self.in_flight.push(InFlight {
+            frame: frame.clone(),
+            sent_at: now,
+            resends: 0,
+        });
+        frame
+    }
+
+    /// Stops waiting for the message with `client_id`. Returns whether it
+    /// was in flight; a second echo of a resent message isn't.
+    pub fn confirm(&mut self, client_id: &str) -> bool {
+        let before = self.in_flight.len();
+        self.in_flight
+            .retain(|m| m.frame.client_id.as_deref() != Some(client_id));
+        self.in_flight.len() != before
+    }
+
+    /// Stops waiting for the oldest message, for servers that don't echo
+    /// client ids and for errors that reject the message in flight.
+    pub fn confirm_oldest(&mut self) -> bool {
+        if self.in_flight.is_empty() {
+            return false;
+        }
+        self.in_flight.remove(0);
+        true
+    }
+
+    /// Collects the messages sent more than `timeout_ms` before `now`:
+    /// those with resends left go out again and wait afresh, the rest are
+    /// dropped as failed.
+    pub fn expire(&mut self, now: f64, timeout_ms: f64) -> Expired {
+        let mut expired = Expired::default();
+        self.in_flight.retain_mut(|m| {
+            if now - m.sent_at < timeout_ms {
+                return true;
+            }
+            if m.resends >= MAX_RESENDS {
+                expired.failed += 1;
+                return false;
+            }
+            m.resends += 1;
+            m.sent_at = now;
+            expired.resend.push(m.frame.clone());
+            true
+        });
+        expired
+    }
+
+    /// When the oldest message runs out of time.
+    pub fn next_deadline(&self, timeout_ms: f64) -> Option<f64> {
+        self.in_flight
+            .iter()
+            .map(|m| m.sent_at + timeout_ms)
+            .reduce(f64::min)
+    }
+
+    /// Restarts every message's wait, e.g. while there's no connection to
+    /// hear back on.
+    pub fn postpone(&mut self, now: f64) {
+        for m in &mut self.in_flight {
+            m.sent_at = now;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+}
@@ -0,0 +1,52 @@
+/// A piece of a message: ordinary text, or a fenced code block.
+#[derive(Debug, PartialEq)]
+pub enum Chunk<'a> {
+    Text(&'a str),
+    /// The lines between a ```` ```lang ```` fence and the closing
+    /// ```` ``` ````, without the fences or the final newline.
+    Code {
+        language: Option<&'a str>,
+        code: &'a str,
+    },
+}
+
+/// Splits out fenced code blocks, as in Markdown: a line starting with
+/// ```` ``` ```` opens one, with anything after the backticks naming its
+/// language, and a line of just ```` ``` ```` closes it. An unclosed fence
+/// is left as text.
+pub fn code_blocks(message: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = vec![];
+    let mut text_start = 0;
+    let mut open: Option<(usize, Option<&str>, usize)> = None;
+    let mut offset = 0;
+    for line in message.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_end();
+        match open {
+            None => {
+                if let Some(info) = trimmed.strip_prefix("```") {
+                    let language = Some(info.trim()).filter(|l| !l.is_empty());
+                    open = Some((start, language, offset));
+                }
+            }
+            Some((fence, language, code_start)) if trimmed == "```" => {
+                if text_start < fence {
+                    chunks.push(Chunk::Text(&message[text_start..fence]));
+                }
+                let code = &message[code_start..start];
+                chunks.push(Chunk::Code {
+                    language,
+                    code: code.strip_suffix('\n').unwrap_or(code),
+                });
+                text_start = offset;
+                open = None;
+            }
+            Some(_) => {}
+        }
+    }
+    if text_start < message.len() {
+        chunks.push(Chunk::Text(&message[text_start..]));
+    }
+    chunks
+}
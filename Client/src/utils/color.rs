@@ -0,0 +1,9 @@
+/// A colour of its own for user `id`, the same on every client and every
+/// visit, as a CSS colour value.
+pub fn username_color(id: &str) -> String {
+    // FNV-1a, for a hash that doesn't change between builds.
+    let hash = id.bytes().fold(0x811c_9dc5u32, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    format!("hsl({}, 65%, 55%)", hash % 360)
+}
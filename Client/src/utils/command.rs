@@ -0,0 +1,31 @@
+/// A `/name args…` line typed into the composer.
+#[derive(Debug, PartialEq)]
+pub struct Command<'a> {
+    /// The name as typed, without the slash.
+    pub name: &'a str,
+    /// Everything after the name, for commands that take free text.
+    pub rest: &'a str,
+    /// `rest` split on whitespace.
+    pub args: Vec<&'a str>,
+}
+
+/// Reads `text` as a command: a slash, then a name of letters, digits, `-`
+/// or `_`. Anything else, e.g. a path such as `/usr/bin`, is plain text.
+pub fn parse_command(text: &str) -> Option<Command<'_>> {
+    let body = text.trim().strip_prefix('/')?;
+    let end = body.find(char::is_whitespace).unwrap_or(body.len());
+    let name = &body[..end];
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+    let rest = body[end..].trim();
+    Some(Command {
+        name,
+        rest,
+        args: rest.split_whitespace().collect(),
+    })
+}
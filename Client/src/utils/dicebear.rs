@@ -0,0 +1,30 @@
+use crate::utils::url::encode_component;
+
+// The avatar style generated for users without a custom avatar.
+const STYLE: &str = "adventurer-neutral";
+
+/// Which DiceBear HTTP API generated avatars come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DicebearVersion {
+    /// The retired `avatars.dicebear.com/api/<style>/<seed>.svg` API, for
+    /// self-hosted mirrors that still serve it.
+    V6,
+    /// `api.dicebear.com/7.x/<style>/svg?seed=<seed>`.
+    #[default]
+    V7,
+}
+
+impl DicebearVersion {
+    /// The generated avatar for `seed`, usually a username.
+    pub fn avatar_url(self, seed: &str) -> String {
+        let seed = encode_component(seed);
+        match self {
+            DicebearVersion::V6 => {
+                format!("https://avatars.dicebear.com/api/{}/{}.svg", STYLE, seed)
+            }
+            DicebearVersion::V7 => {
+                format!("https://api.dicebear.com/7.x/{}/svg?seed={}", STYLE, seed)
+            }
+        }
+    }
+}
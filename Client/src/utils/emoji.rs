@@ -0,0 +1,94 @@
+/// The emoji the picker offers, by shortcode.
+pub const EMOJI: &[(&str, &str)] = &[
+    ("smile", "😄"),
+    ("grin", "😁"),
+    ("joy", "😂"),
+    ("wink", "😉"),
+    ("blush", "😊"),
+    ("heart_eyes", "😍"),
+    ("thinking", "🤔"),
+    ("neutral_face", "😐"),
+    ("roll_eyes", "🙄"),
+    ("sweat_smile", "😅"),
+    ("sob", "😭"),
+    ("cry", "😢"),
+    ("angry", "😠"),
+    ("scream", "😱"),
+    ("sunglasses", "😎"),
+    ("sleeping", "😴"),
+    ("party", "🥳"),
+    ("wave", "👋"),
+    ("thumbsup", "👍"),
+    ("thumbsdown", "👎"),
+    ("clap", "👏"),
+    ("pray", "🙏"),
+    ("muscle", "💪"),
+    ("ok_hand", "👌"),
+    ("eyes", "👀"),
+    ("heart", "❤️"),
+    ("broken_heart", "💔"),
+    ("fire", "🔥"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("tada", "🎉"),
+    ("rocket", "🚀"),
+    ("check", "✅"),
+    ("x", "❌"),
+    ("warning", "⚠️"),
+    ("question", "❓"),
+    ("coffee", "☕"),
+    ("pizza", "🍕"),
+    ("cake", "🎂"),
+    ("bug", "🐛"),
+];
+
+/// The emoji whose shortcode contains `query`, ignoring ASCII case and any
+/// colons typed around it, in picker order.
+pub fn search(query: &str) -> Vec<(&'static str, &'static str)> {
+    let query = query.trim().trim_matches(':').to_ascii_lowercase();
+    EMOJI
+        .iter()
+        .filter(|(code, _)| code.contains(query.as_str()))
+        .copied()
+        .collect()
+}
+
+/// A move of the selection through a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridMove {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl GridMove {
+    /// The move an arrow key makes, from its `KeyboardEvent.key`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "ArrowLeft" => Some(GridMove::Left),
+            "ArrowRight" => Some(GridMove::Right),
+            "ArrowUp" => Some(GridMove::Up),
+            "ArrowDown" => Some(GridMove::Down),
+            _ => None,
+        }
+    }
+}
+
+/// The selection after `step` from `index` in a grid of `len` cells laid
+/// out `columns` to a row. Left and right wrap between rows; up and down
+/// stay put at the top and bottom edge.
+pub fn move_selection(index: usize, len: usize, columns: usize, step: GridMove) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let index = index.min(len - 1);
+    match step {
+        GridMove::Left => index.checked_sub(1).unwrap_or(len - 1),
+        GridMove::Right => (index + 1) % len,
+        GridMove::Up => index.checked_sub(columns).unwrap_or(index),
+        // The last row may be short; moving into it lands on its last cell.
+        GridMove::Down if index / columns < (len - 1) / columns => (index + columns).min(len - 1),
+        GridMove::Down => index,
+    }
+}
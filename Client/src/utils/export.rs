@@ -0,0 +1,146 @@
+use serde::Serialize;
+
+use crate::services::protocol::{Category, MessageData};
+
+/// How a history export is written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// Structured, for archiving and other tools.
+    Json,
+    /// A plain transcript for people to read.
+    Text,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Text => "Transcript",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Text => "txt",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Text => "text/plain;charset=utf-8",
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Export<'a> {
+    room: &'a str,
+    exported_at: String,
+    messages: Vec<ExportedMessage<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedMessage<'a> {
+    id: Option<&'a str>,
+    /// UTC, ISO 8601.
+    time: Option<String>,
+    from: &'a str,
+    display_name: &'a str,
+    message: &'a str,
+    edited: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    attachments: &'a [String],
+    /// Ids of identical messages shown collapsed into this one.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    duplicates: &'a [String],
+}
+
+// Chat messages that still have text; notices and deleted messages aren't
+// part of the record.
+fn exported(messages: &[MessageData]) -> impl Iterator<Item = &MessageData> {
+    messages
+        .iter()
+        .filter(|m| m.category == Category::Chat && !m.deleted)
+}
+
+fn display_name(m: &MessageData) -> &str {
+    m.display_name.as_deref().unwrap_or(&m.from)
+}
+
+/// The messages of `room` as a JSON document, exported at `now_ms`.
+pub fn to_json(room: &str, messages: &[MessageData], now_ms: f64) -> serde_json::Result<String> {
+    let export = Export {
+        room,
+        exported_at: iso_timestamp(now_ms),
+        messages: exported(messages)
+            .map(|m| ExportedMessage {
+                id: m.id.as_deref(),
+                time: m.time.map(iso_timestamp),
+                from: &m.from,
+                display_name: display_name(m),
+                message: &m.message,
+                edited: m.edited,
+                attachments: &m.attachments,
+                duplicates: &m.duplicates,
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&export)
+}
+
+/// The messages of `room` as one `[time] name: text` line each, with
+/// continuation lines indented. `stamp` formats a message's timestamp.
+pub fn to_transcript(
+    room: &str,
+    messages: &[MessageData],
+    stamp: impl Fn(f64) -> String,
+) -> String {
+    let mut out = format!("{}\n\n", room);
+    for m in exported(messages) {
+        let time = m.time.map(&stamp).unwrap_or_else(|| "unknown time".into());
+        let text = m.message.replace('\n', "\n    ");
+        out += &format!("[{}] {}: {}", time, display_name(m), text);
+        if m.edited {
+            out += " (edited)";
+        }
+        if !m.duplicates.is_empty() {
+            out += &format!(" (×{})", m.duplicates.len() + 1);
+        }
+        for url in &m.attachments {
+            out += &format!("\n    📎 {}", url);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// A unix timestamp in milliseconds as UTC ISO 8601, e.g.
+/// `2024-03-01T09:05:00.000Z`.
+pub fn iso_timestamp(epoch_ms: f64) -> String {
+    let ms = epoch_ms as i64;
+    let (days, ms_of_day) = (ms.div_euclid(86_400_000), ms.rem_euclid(86_400_000));
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1_000 % 60,
+        ms_of_day % 1_000
+    )
+}
@@ -0,0 +1,61 @@
+/// Inline markdown the composer toolbar can apply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Bold,
+    Italic,
+    Code,
+}
+
+impl Style {
+    pub fn delimiter(self) -> &'static str {
+        match self {
+            Style::Bold => "**",
+            Style::Italic => "*",
+            Style::Code => "`",
+        }
+    }
+}
+
+/// Wraps the selection `start..end` of `text` in `style`'s delimiters, or
+/// inserts an empty pair at the cursor when nothing is selected. Offsets are
+/// in UTF-16 code units, as the DOM selection APIs report them. Returns the
+/// new text and the selection to restore, which covers the same characters
+/// as before (or sits between the empty pair).
+pub fn wrap_selection(text: &str, start: u32, end: u32, style: Style) -> (String, u32, u32) {
+    let (start, end) = (start.min(end), start.max(end));
+    let at = |offset: u32| byte_index(text, offset);
+    let (from, to) = (at(start), at(end));
+    let delimiter = style.delimiter();
+    let wrapped = format!(
+        "{}{}{}{}{}",
+        &text[..from],
+        delimiter,
+        &text[from..to],
+        delimiter,
+        &text[to..]
+    );
+    let shift = delimiter.len() as u32;
+    (wrapped, start + shift, end + shift)
+}
+
+/// Replaces the selection `start..end` of `text` with `insert`, in UTF-16
+/// offsets like `wrap_selection`. Returns the new text and the cursor
+/// position just after the inserted text.
+pub fn insert_at(text: &str, start: u32, end: u32, insert: &str) -> (String, u32) {
+    let (start, end) = (start.min(end), start.max(end));
+    let (from, to) = (byte_index(text, start), byte_index(text, end));
+    let inserted = format!("{}{}{}", &text[..from], insert, &text[to..]);
+    (inserted, start + insert.encode_utf16().count() as u32)
+}
+
+// The byte index of UTF-16 offset `offset`, clamped to the text.
+fn byte_index(text: &str, offset: u32) -> usize {
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= offset as usize {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
@@ -0,0 +1,74 @@
+use std::cmp::Reverse;
+
+// Points for each matched character, and extra for the kinds of match people
+// expect to rank first.
+const MATCH: u32 = 1;
+const CONSECUTIVE: u32 = 5;
+const WORD_START: u32 = 4;
+const FIRST_CHARACTER: u32 = 8;
+
+/// How well `query` matches `candidate` as a subsequence, ignoring case, or
+/// `None` when it doesn't match at all. Higher is better: matches that run
+/// together or start words ("ab" in "Alice Brown") beat scattered ones. An
+/// empty query matches everything with a score of 0.
+///
+/// Each candidate is scanned once, so this stays cheap across a large roster.
+pub fn score(query: &str, candidate: &str) -> Option<u32> {
+    let mut wanted = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    let mut total = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for (i, c) in candidate.chars().enumerate() {
+        let next = match wanted.peek() {
+            Some(&next) => next,
+            None => break,
+        };
+        let matched = c.to_lowercase().eq(std::iter::once(next));
+        if matched {
+            wanted.next();
+            total += MATCH;
+            if i == 0 {
+                total += FIRST_CHARACTER;
+            }
+            if previous_matched {
+                total += CONSECUTIVE;
+            }
+            if starts_word(previous, c) {
+                total += WORD_START;
+            }
+        }
+        previous_matched = matched;
+        previous = Some(c);
+    }
+    wanted.peek().is_none().then_some(total)
+}
+
+/// The best score of `query` against any of `candidates`.
+pub fn best_score(query: &str, candidates: &[&str]) -> Option<u32> {
+    candidates
+        .iter()
+        .filter_map(|candidate| score(query, candidate))
+        .max()
+}
+
+/// The items `score_of` matches, best first. Ties keep their original order.
+pub fn rank<T>(items: impl IntoIterator<Item = T>, score_of: impl Fn(&T) -> Option<u32>) -> Vec<T> {
+    let mut scored: Vec<(u32, T)> = items
+        .into_iter()
+        .filter_map(|item| score_of(&item).map(|score| (score, item)))
+        .collect();
+    scored.sort_by_key(|&(score, _)| Reverse(score));
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+// A character starts a word after a separator or as the capital in camelCase.
+fn starts_word(previous: Option<char>, c: char) -> bool {
+    match previous {
+        None => true,
+        Some(p) => !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase()),
+    }
+}
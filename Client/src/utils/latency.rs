@@ -0,0 +1,60 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Round-trip samples averaged for the latency shown in the header.
+const WINDOW: usize = 5;
+/// Pings still waiting for a pong beyond this many are forgotten.
+const MAX_PENDING: usize = 8;
+
+/// Measures round-trip time from heartbeat pings and their pongs.
+#[derive(Default)]
+pub struct Latency {
+    next_id: u32,
+    // Ping id -> when it was sent, in milliseconds.
+    pending: HashMap<u32, f64>,
+    samples: VecDeque<f64>,
+}
+
+impl Latency {
+    /// Records a ping sent at `now` and returns the id to send with it.
+    pub fn ping(&mut self, now: f64) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending.insert(id, now);
+        if self.pending.len() > MAX_PENDING {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                self.pending.remove(&oldest);
+            }
+        }
+        id
+    }
+
+    /// Records the pong for ping `id` and returns its round-trip time, or
+    /// `None` for a pong we didn't ask for.
+    pub fn pong(&mut self, id: u32, now: f64) -> Option<f64> {
+        let rtt = (now - self.pending.remove(&id)?).max(0.0);
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rtt);
+        Some(rtt)
+    }
+
+    /// The average of the last few round trips.
+    pub fn average(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+
+    /// Forgets pings sent on a connection that has since dropped; their
+    /// pongs will never come.
+    pub fn reset_pending(&mut self) {
+        self.pending.clear();
+    }
+}
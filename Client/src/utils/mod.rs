@@ -0,0 +1,16 @@
+pub mod code;
+pub mod color;
+pub mod command;
+pub mod dicebear;
+pub mod emoji;
+pub mod export;
+pub mod format;
+pub mod fuzzy;
+pub mod latency;
+pub mod quote;
+pub mod time;
+pub mod tokenize;
+pub mod typing;
+pub mod url;
+pub mod username;
+pub mod word_filter;
\ No newline at end of file
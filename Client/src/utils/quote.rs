@@ -0,0 +1,40 @@
+/// Turns `text` into a quote to reply beneath: every line prefixed with `> `
+/// and a final newline so the reply starts on a line of its own.
+pub fn quote(text: &str) -> String {
+    let mut quoted = text
+        .trim_end()
+        .lines()
+        .map(|line| format!("> {}", line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    quoted.push('\n');
+    quoted
+}
+
+/// A run of consecutive lines that are either all quoted or all not. Quoted
+/// blocks have their `>` markers removed.
+#[derive(Debug, PartialEq)]
+pub struct Block {
+    pub quoted: bool,
+    pub text: String,
+}
+
+/// Splits a message into quoted and unquoted blocks, as in Markdown: a line
+/// starting with `>` is quoted, with one space after the marker dropped.
+pub fn blocks(message: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = vec![];
+    for line in message.split_inclusive('\n') {
+        let (quoted, text) = match line.strip_prefix('>') {
+            Some(rest) => (true, rest.strip_prefix(' ').unwrap_or(rest)),
+            None => (false, line),
+        };
+        match blocks.last_mut() {
+            Some(block) if block.quoted == quoted => block.text.push_str(text),
+            _ => blocks.push(Block {
+                quoted,
+                text: text.to_string(),
+            }),
+        }
+    }
+    blocks
+}
@@ -0,0 +1,140 @@
+use js_sys::{Array, Date, Intl, Object, Reflect};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    H12,
+    H24,
+}
+
+impl TimeFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeFormat::H12 => "12h",
+            TimeFormat::H24 => "24h",
+        }
+    }
+
+    /// The hour cycle the browser locale uses by default.
+    pub fn from_locale() -> Self {
+        let options = Object::new();
+        let _ = Reflect::set(&options, &"hour".into(), &"numeric".into());
+        let resolved = Intl::DateTimeFormat::new(&Array::new(), &options).resolved_options();
+        match Reflect::get(&resolved, &"hour12".into()).map(|v| v.as_bool()) {
+            Ok(Some(true)) => TimeFormat::H12,
+            _ => TimeFormat::H24,
+        }
+    }
+}
+
+/// Formats a unix timestamp in milliseconds as a local wall-clock time.
+pub fn format_time(epoch_ms: f64, format: TimeFormat) -> String {
+    let (hours, minutes) = clock_time(epoch_ms, local_offset(epoch_ms));
+    format_clock(hours, minutes, format)
+}
+
+/// The `(hours, minutes)` on a clock `offset_minutes` east of UTC at a
+/// unix timestamp in milliseconds.
+pub fn clock_time(epoch_ms: f64, offset_minutes: i32) -> (u32, u32) {
+    let minutes = (epoch_ms / 60_000.0).floor() as i64 + i64::from(offset_minutes);
+    let of_day = minutes.rem_euclid(24 * 60);
+    ((of_day / 60) as u32, (of_day % 60) as u32)
+}
+
+// The browser's UTC offset at `epoch_ms`, which moves with daylight saving.
+fn local_offset(epoch_ms: f64) -> i32 {
+    -Date::new(&JsValue::from_f64(epoch_ms)).get_timezone_offset() as i32
+}
+
+/// Formats a unix timestamp in milliseconds as a full local date and time,
+/// for tooltips where the clock time alone is ambiguous.
+pub fn format_date_time(epoch_ms: f64, format: TimeFormat) -> String {
+    let date = Date::new(&JsValue::from_f64(epoch_ms));
+    format_date(date.get_full_year(), date.get_month() + 1, date.get_date())
+        + " "
+        + &format_time(epoch_ms, format)
+}
+
+/// The local calendar day of a unix timestamp in milliseconds, as
+/// `(year, month, day)`.
+pub fn local_day(epoch_ms: f64) -> (u32, u32, u32) {
+    let date = Date::new(&JsValue::from_f64(epoch_ms));
+    (date.get_full_year(), date.get_month() + 1, date.get_date())
+}
+
+/// The current unix time in milliseconds. Outside the browser, as in
+/// tests, this reads the system clock.
+pub fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1_000.0)
+    }
+}
+
+/// `time` as a message timestamp: `None` when it can't be a real time, and
+/// `now` when it is in the future, as a fast clock on either end puts it.
+pub fn normalize_timestamp(time: f64, now: f64) -> Option<f64> {
+    (time.is_finite() && time >= 0.0).then(|| time.min(now))
+}
+
+/// Where a list of entries starts a new day, given each entry's day (any
+/// ordered key, such as a `local_day`) or `None` for entries without a
+/// time. Each day is announced once, at the first entry later than every
+/// day before it, so entries that arrive out of order sit under the
+/// current day instead of repeating an earlier separator.
+pub fn day_breaks<T: Ord + Copy>(days: impl IntoIterator<Item = Option<T>>) -> Vec<Option<T>> {
+    let mut latest = None;
+    days.into_iter()
+        .map(|day| {
+            let day = day.filter(|&day| latest.is_none_or(|latest| day > latest))?;
+            latest = Some(day);
+            Some(day)
+        })
+        .collect()
+}
+
+/// Time left in its two largest units, e.g. `45s`, `4m 12s` or `2d 3h`,
+/// for disappearing messages. Rounds up, so nothing shows `0s` while it
+/// is still there.
+pub fn format_countdown(remaining_ms: f64) -> String {
+    let seconds = (remaining_ms.max(0.0) / 1_000.0).ceil() as u64;
+    let units = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+    let i = units
+        .iter()
+        .position(|&(size, _)| seconds >= size)
+        .unwrap_or(units.len() - 1);
+    let (size, suffix) = units[i];
+    let mut out = format!("{}{}", seconds / size, suffix);
+    if let Some(&(smaller, suffix)) = units.get(i + 1) {
+        let rest = seconds % size / smaller;
+        if rest > 0 {
+            out += &format!(" {}{}", rest, suffix);
+        }
+    }
+    out
+}
+
+pub fn format_date(year: u32, month: u32, day: u32) -> String {
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+pub fn format_clock(hours: u32, minutes: u32, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::H24 => format!("{:02}:{:02}", hours, minutes),
+        TimeFormat::H12 => {
+            let suffix = if hours < 12 { "AM" } else { "PM" };
+            let hours = match hours % 12 {
+                0 => 12,
+                h => h,
+            };
+            format!("{}:{:02} {}", hours, minutes, suffix)
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::utils::url::{is_http_url, is_image_url};
+
+/// A piece of a chat message, as far as rendering is concerned.
+#[derive(Debug, PartialEq)]
+pub enum Segment<'a> {
+    /// Plain text, including any surrounding whitespace.
+    Text(&'a str),
+    /// An http(s) URL pointing at an image.
+    Image(&'a str),
+    /// A markdown link, `[label](url)`. Only http(s) URLs become links, so
+    /// `javascript:` and friends stay plain text.
+    Link { label: &'a str, url: &'a str },
+    /// `@name` at the start of a word; holds the name without the `@`.
+    Mention(&'a str),
+    /// `@everyone` or `@here`, addressing the whole room.
+    Broadcast(&'a str),
+    /// `**text**`; holds the text between the delimiters.
+    Bold(&'a str),
+    /// `*text*`.
+    Italic(&'a str),
+    /// `` `text` ``, shown verbatim.
+    Code(&'a str),
+}
+
+/// Mentions that address everyone rather than a user by that name.
+pub const BROADCAST_MENTIONS: &[&str] = &["everyone", "here"];
+
+/// Splits a message into text, markdown links and emphasis, mentions and the
+/// image URLs inside it. Whitespace stays with the surrounding text.
+pub fn tokenize(message: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut text_start = 0;
+    let mut search = 0;
+    while let Some(open) = message[search..].find(['[', '*', '`']).map(|i| search + i) {
+        let rest = &message[open..];
+        let parsed = if rest.starts_with('[') {
+            parse_link(rest).map(|(label, url, len)| (Segment::Link { label, url }, len))
+        } else {
+            parse_emphasis(rest)
+        };
+        match parsed {
+            Some((segment, len)) => {
+                split_words(&message[text_start..open], &mut segments);
+                segments.push(segment);
+                text_start = open + len;
+                search = text_start;
+            }
+            None => search = open + 1,
+        }
+    }
+    split_words(&message[text_start..], &mut segments);
+    segments
+}
+
+/// Whether `message` contains `@everyone` or `@here`.
+pub fn mentions_everyone(message: &str) -> bool {
+    tokenize(message)
+        .iter()
+        .any(|segment| matches!(segment, Segment::Broadcast(_)))
+}
+
+/// Whether `message` mentions `name`, ignoring ASCII case.
+pub fn mentions(message: &str, name: &str) -> bool {
+    tokenize(message)
+        .iter()
+        .any(|segment| matches!(segment, Segment::Mention(m) if m.eq_ignore_ascii_case(name)))
+}
+
+/// The name being typed when `text` ends in an unfinished mention, such as
+/// `"bo"` for `"thanks @bo"`. A lone `@` gives `""`.
+pub fn pending_mention(text: &str) -> Option<&str> {
+    if text.ends_with(char::is_whitespace) {
+        return None;
+    }
+    let word = text.rsplit(char::is_whitespace).next()?;
+    let name = word.strip_prefix('@')?;
+    name.chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .then_some(name)
+}
+
+// Parses `[label](url)` at the start of `s`, returning the label, the URL and
+// how many bytes the link spans.
+fn parse_link(s: &str) -> Option<(&str, &str, usize)> {
+    let close = s.find(']')?;
+    let label = &s[1..close];
+    // A `[` inside the label means this bracket isn't the one that opens the
+    // link; the caller retries from the inner one.
+    if label.trim().is_empty() || label.contains(['[', '\n']) {
+        return None;
+    }
+    let rest = s[close + 1..].strip_prefix('(')?;
+    let end = rest.find(')')?;
+    let url = &rest[..end];
+    if !is_http_url(url) {
+        return None;
+    }
+    Some((label, url, close + 2 + end + 1))
+}
+
+// Parses `**bold**`, `*italic*` or `` `code` `` at the start of `s`, returning
+// the segment and how many bytes it spans. Like markdown, the text may not
+// start or end with whitespace, so `2 * 3 * 4` stays plain, and emphasis
+// never spans lines.
+fn parse_emphasis<'a>(s: &'a str) -> Option<(Segment<'a>, usize)> {
+    let (delimiter, segment): (&str, fn(&'a str) -> Segment<'a>) = if s.starts_with('`') {
+        ("`", Segment::Code)
+    } else if s.starts_with("**") {
+        ("**", Segment::Bold)
+    } else {
+        ("*", Segment::Italic)
+    };
+    let rest = &s[delimiter.len()..];
+    let end = rest.find(delimiter)?;
+    let inner = &rest[..end];
+    let padded = inner.starts_with(char::is_whitespace) || inner.ends_with(char::is_whitespace);
+    if inner.is_empty() || inner.contains('\n') || (delimiter != "`" && padded) {
+        return None;
+    }
+    Some((segment(inner), end + 2 * delimiter.len()))
+}
+
+// Picks image URLs and mentions out of text that holds no links.
+fn split_words<'a>(text: &'a str, segments: &mut Vec<Segment<'a>>) {
+    let mut text_start = 0;
+    for word in text.split_whitespace() {
+        let start = word.as_ptr() as usize - text.as_ptr() as usize;
+        let (segment, len) = if is_image_url(word) {
+            (Segment::Image(word), word.len())
+        } else if let Some(name) = mention(word) {
+            let broadcast = BROADCAST_MENTIONS
+                .iter()
+                .any(|b| b.eq_ignore_ascii_case(name));
+            let segment = if broadcast {
+                Segment::Broadcast(name)
+            } else {
+                Segment::Mention(name)
+            };
+            (segment, name.len() + 1)
+        } else {
+            continue;
+        };
+        if start > text_start {
+            segments.push(Segment::Text(&text[text_start..start]));
+        }
+        segments.push(segment);
+        text_start = start + len;
+    }
+    if text_start < text.len() {
+        segments.push(Segment::Text(&text[text_start..]));
+    }
+}
+
+// The name in a word like `@bob,`: letters, digits and `_-.`, minus any
+// trailing dots so the end of a sentence isn't part of the name.
+fn mention(word: &str) -> Option<&str> {
+    let rest = word.strip_prefix('@')?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+        .unwrap_or(rest.len());
+    let name = rest[..end].trim_end_matches('.');
+    (!name.is_empty()).then_some(name)
+}
@@ -0,0 +1,60 @@
+// Names shown before the rest are summed up as "N others".
+const MAX_NAMED_TYPERS: usize = 2;
+
+/// Describes who is typing, e.g. "Alice, Bob and 3 others are typing…".
+/// Returns `None` when nobody is.
+pub fn typing_summary(names: &[&str]) -> Option<String> {
+    let subject = match names {
+        [] => return None,
+        [one] => return Some(format!("{} is typing…", one)),
+        [first, second] => format!("{} and {}", first, second),
+        _ => {
+            let others = names.len() - MAX_NAMED_TYPERS;
+            format!(
+                "{} and {} {}",
+                names[..MAX_NAMED_TYPERS].join(", "),
+                others,
+                if others == 1 { "other" } else { "others" }
+            )
+        }
+    };
+    Some(format!("{} are typing…", subject))
+}
+
+/// Whether others are told we're typing: while the composer is in use, but
+/// never while we're away, so someone who walked off mid-message doesn't
+/// look like they're still writing.
+#[derive(Debug, Default)]
+pub struct TypingSignal {
+    typing: bool,
+    away: bool,
+}
+
+impl TypingSignal {
+    /// Whether the indicator is showing for others.
+    pub fn is_shown(&self) -> bool {
+        self.typing && !self.away
+    }
+
+    /// Records typing starting or stopping; typing while away isn't kept.
+    /// Returns the state to send when what others see changes.
+    pub fn set_typing(&mut self, typing: bool) -> Option<bool> {
+        self.update(|s| s.typing = typing && !s.away)
+    }
+
+    /// Records us going away or coming back. Going away stops the
+    /// indicator at once; coming back leaves it off until we type again.
+    pub fn set_away(&mut self, away: bool) -> Option<bool> {
+        self.update(|s| {
+            s.away = away;
+            s.typing &= !away;
+        })
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut Self)) -> Option<bool> {
+        let before = self.is_shown();
+        change(self);
+        let after = self.is_shown();
+        (before != after).then_some(after)
+    }
+}
@@ -0,0 +1,107 @@
+const IMAGE_EXTENSIONS: &[&str] = &[".png", ".jpg", ".jpeg", ".gif", ".webp", ".svg"];
+
+pub fn is_http_url(url: &str) -> bool {
+    (url.starts_with("https://") || url.starts_with("http://"))
+        && !url.chars().any(char::is_whitespace)
+}
+
+/// A short name for a linked file: the last path segment of `url`, or its
+/// host when the path is empty.
+pub fn file_name(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = rest.split(['?', '#']).next().unwrap_or(rest);
+    let path = path.trim_end_matches('/');
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// `url` with `name=value` added to its query string, after any parameters
+/// it already has and before its fragment.
+pub fn with_query_param(url: &str, name: &str, value: &str) -> String {
+    let (rest, fragment) = match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    };
+    let separator = if rest.contains('?') { '&' } else { '?' };
+    format!("{}{}{}={}{}", rest, separator, name, value, fragment)
+}
+
+/// The fragment that links to a message, e.g. `msg-42`.
+pub fn message_anchor(id: &str) -> String {
+    format!("msg-{}", id)
+}
+
+/// The message id a `location.hash` such as `#msg-42` links to.
+pub fn anchored_message(hash: &str) -> Option<&str> {
+    hash.strip_prefix("#msg-").filter(|id| !id.is_empty())
+}
+
+/// Whether `url` is an http(s) URL whose path ends in a known image extension.
+pub fn is_image_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let path = path.to_ascii_lowercase();
+    is_http_url(url) && IMAGE_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+/// A file name to save `url` under: its last path segment, percent-decoded,
+/// with characters file systems reject replaced by `_`. Falls back to
+/// `image` when nothing usable is left.
+pub fn download_name(url: &str) -> String {
+    let name = file_name(url);
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .filter(|_| byte == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let name: String = String::from_utf8_lossy(&bytes)
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_matches('.');
+    if name.is_empty() {
+        "image".into()
+    } else {
+        name.into()
+    }
+}
+
+/// `s` percent-encoded for use in a query string value, like JavaScript's
+/// `encodeURIComponent`.
+pub fn encode_component(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'_'
+            | b'.'
+            | b'!'
+            | b'~'
+            | b'*'
+            | b'\''
+            | b'('
+            | b')' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
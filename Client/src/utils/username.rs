@@ -0,0 +1,16 @@
+pub const MAX_USERNAME_LEN: usize = 32;
+
+/// Trims `name` and checks it is usable as a chat username.
+pub fn validate_username(name: &str) -> Result<String, &'static str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Username can't be empty");
+    }
+    if name.chars().count() > MAX_USERNAME_LEN {
+        return Err("Username must be at most 32 characters");
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Username contains invalid characters");
+    }
+    Ok(name.to_string())
+}
@@ -0,0 +1,62 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// Masks listed words in message text, for people who would rather not see
+/// them. Matching ignores case and takes whole words only, so filtering
+/// "ass" leaves "class" alone.
+#[derive(Debug, Default, PartialEq)]
+pub struct WordFilter {
+    // Lowercased, so each word of a message is a single lookup.
+    words: HashSet<String>,
+    longest: usize,
+}
+
+impl WordFilter {
+    /// Blank entries are ignored, and surrounding spaces trimmed.
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        let words: HashSet<String> = words
+            .iter()
+            .map(|w| w.as_ref().trim().to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let longest = words.iter().map(|w| w.chars().count()).max().unwrap_or(0);
+        Self { words, longest }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// `text` with every listed word replaced by an `*` per character.
+    /// Borrowed when nothing matched.
+    pub fn mask<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut masked = String::new();
+        // Up to where `text` has been copied into `masked`.
+        let mut copied = 0;
+        let mut start = None;
+        for (i, c) in text
+            .char_indices()
+            .chain(std::iter::once((text.len(), ' ')))
+        {
+            match (start, c.is_alphanumeric()) {
+                (None, true) => start = Some(i),
+                (Some(from), false) => {
+                    start = None;
+                    let word = &text[from..i];
+                    let len = word.chars().count();
+                    if len <= self.longest && self.words.contains(&word.to_lowercase()) {
+                        masked.push_str(&text[copied..from]);
+                        masked.push_str(&"*".repeat(len));
+                        copied = i;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        masked.push_str(&text[copied..]);
+        Cow::Owned(masked)
+    }
+}
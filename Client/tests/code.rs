@@ -0,0 +1,44 @@
+use yewchat::utils::code::{code_blocks, Chunk};
+
+#[test]
+fn fences_split_out_code_with_its_language() {
+    let message = "try this:\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\nthen run it";
+    assert_eq!(
+        code_blocks(message),
+        [
+            Chunk::Text("try this:\n"),
+            Chunk::Code {
+                language: Some("rust"),
+                code: "fn main() {\n    println!(\"hi\");\n}",
+            },
+            Chunk::Text("then run it"),
+        ]
+    );
+}
+
+#[test]
+fn fences_without_a_language_or_surrounding_text() {
+    assert_eq!(
+        code_blocks("```\nls -la\n```"),
+        [Chunk::Code {
+            language: None,
+            code: "ls -la",
+        }]
+    );
+    assert_eq!(
+        code_blocks("```\n```"),
+        [Chunk::Code {
+            language: None,
+            code: "",
+        }]
+    );
+}
+
+#[test]
+fn inline_code_and_unclosed_fences_stay_text() {
+    assert_eq!(code_blocks("use `ls` here"), [Chunk::Text("use `ls` here")]);
+    assert_eq!(
+        code_blocks("```\nnever closed"),
+        [Chunk::Text("```\nnever closed")]
+    );
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::frame;
+use yewchat::services::codec::{self, Codec, JsonCodec, LineCodec, Payload};
+use yewchat::services::protocol::{MessageData, MsgTypes, WebSocketMessage};
+use yewchat::services::websocket::WsError;
+
+#[test]
+fn json_codec_round_trips() {
+    let encoded = JsonCodec
+        .encode(&frame(MsgTypes::Register, "alice"))
+        .unwrap();
+    assert_eq!(
+        encoded,
+        r#"{"messageType":"register","dataArray":null,"data":"alice"}"#
+    );
+    let decoded = JsonCodec.decode(&encoded).unwrap();
+    assert!(matches!(decoded.message_type, MsgTypes::Register));
+    assert_eq!(decoded.data.as_deref(), Some("alice"));
+    assert!(JsonCodec.decode("register alice").is_err());
+}
+
+#[test]
+fn line_codec_encodes_type_and_escaped_data() {
+    assert_eq!(
+        LineCodec
+            .encode(&frame(MsgTypes::Register, "alice"))
+            .unwrap(),
+        "register alice"
+    );
+    assert_eq!(
+        LineCodec
+            .encode(&frame(MsgTypes::Message, "two\nlines \\ here"))
+            .unwrap(),
+        "message two\\nlines \\\\ here"
+    );
+    assert_eq!(
+        LineCodec
+            .encode(&WebSocketMessage::new(MsgTypes::History, None))
+            .unwrap(),
+        "history"
+    );
+}
+
+#[test]
+fn line_codec_decodes_roster_and_messages() {
+    let users = LineCodec.decode("users alice bob\n").unwrap();
+    assert!(matches!(users.message_type, MsgTypes::Users));
+    assert_eq!(users.data_array.unwrap(), ["alice", "bob"]);
+
+    let message = LineCodec.decode("message bob hi\\nthere").unwrap();
+    assert!(matches!(message.message_type, MsgTypes::Message));
+    let data: MessageData = serde_json::from_str(&message.data.unwrap()).unwrap();
+    assert_eq!(data.from, "bob");
+    assert_eq!(data.message, "hi\nthere");
+
+    let delete = LineCodec.decode("delete 4").unwrap();
+    assert_eq!(delete.data.as_deref(), Some("4"));
+}
+
+#[test]
+fn line_codec_rejects_bad_lines() {
+    assert!(LineCodec.decode("").is_err());
+    assert!(LineCodec.decode("shout hello").is_err());
+    assert!(LineCodec.decode("message bob").is_err());
+}
+
+// Stands in for a compact binary format: the JSON frame with a marker byte.
+struct TaggedCodec;
+
+impl Codec for TaggedCodec {
+    fn encode(&self, msg: &WebSocketMessage) -> Result<String, WsError> {
+        JsonCodec.encode(msg)
+    }
+
+    fn decode(&self, raw: &str) -> Result<WebSocketMessage, String> {
+        JsonCodec.decode(raw)
+    }
+
+    fn encode_binary(&self, msg: &WebSocketMessage) -> Option<Vec<u8>> {
+        let mut bytes = vec![0xff];
+        bytes.extend(self.encode(msg).ok()?.into_bytes());
+        Some(bytes)
+    }
+
+    fn decode_binary(&self, bytes: &[u8]) -> Result<WebSocketMessage, String> {
+        match bytes.split_first() {
+            Some((0xff, rest)) => self.decode(std::str::from_utf8(rest).unwrap()),
+            _ => Err("missing marker".into()),
+        }
+    }
+}
+
+#[test]
+fn text_codecs_send_text_and_accept_utf8_binary() {
+    let msg = frame(MsgTypes::Register, "alice");
+    assert_eq!(
+        LineCodec.encode_payload(&msg),
+        Ok(Payload::Text("register alice".into()))
+    );
+    let decoded = LineCodec
+        .decode_payload(&Payload::Binary(b"delete 4".to_vec()))
+        .unwrap();
+    assert!(matches!(decoded.message_type, MsgTypes::Delete));
+    assert!(JsonCodec
+        .decode_payload(&Payload::Binary(vec![0xc3, 0x28]))
+        .is_err());
+}
+
+#[test]
+fn binary_codecs_round_trip_through_payloads() {
+    let payload = TaggedCodec
+        .encode_payload(&frame(MsgTypes::Message, "hi"))
+        .unwrap();
+    match &payload {
+        Payload::Binary(bytes) => assert_eq!(bytes[0], 0xff),
+        Payload::Text(_) => panic!("expected a binary payload"),
+    }
+    let decoded = TaggedCodec.decode_payload(&payload).unwrap();
+    assert_eq!(decoded.data.as_deref(), Some("hi"));
+}
+
+// Stands in for a payload whose `Serialize` impl fails, e.g. a map with
+// non-string keys.
+struct Unserializable;
+
+impl serde::Serialize for Unserializable {
+    fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("not today"))
+    }
+}
+
+// A codec that can never encode, to exercise the send error path.
+struct FailingCodec;
+
+impl Codec for FailingCodec {
+    fn encode(&self, _msg: &WebSocketMessage) -> Result<String, WsError> {
+        codec::to_json(&Unserializable)
+    }
+
+    fn decode(&self, raw: &str) -> Result<WebSocketMessage, String> {
+        JsonCodec.decode(raw)
+    }
+}
+
+#[test]
+fn serialization_failures_are_errors_not_panics() {
+    assert_eq!(
+        codec::to_json(&Unserializable),
+        Err(WsError::Serialize("not today".into()))
+    );
+    let msg = frame(MsgTypes::Message, "hi");
+    assert!(matches!(
+        FailingCodec.encode_payload(&msg),
+        Err(WsError::Serialize(_))
+    ));
+}
+
+#[test]
+fn line_codec_appends_attachments_to_the_text() {
+    let mut msg = frame(MsgTypes::Message, "look");
+    msg.data_array = Some(vec!["https://a.example/1.png".into()]);
+    assert_eq!(
+        LineCodec.encode(&msg).unwrap(),
+        "message look https://a.example/1.png"
+    );
+    msg.data = Some(String::new());
+    assert_eq!(
+        LineCodec.encode(&msg).unwrap(),
+        "message https://a.example/1.png"
+    );
+}
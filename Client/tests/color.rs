@@ -0,0 +1,9 @@
+use yewchat::utils::color::username_color;
+
+#[test]
+fn users_keep_their_own_colour() {
+    assert_eq!(username_color("alice"), username_color("alice"));
+    assert_ne!(username_color("alice"), username_color("bob"));
+    let colour = username_color("alice");
+    assert!(colour.starts_with("hsl(") && colour.ends_with(", 65%, 55%)"));
+}
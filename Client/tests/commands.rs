@@ -0,0 +1,42 @@
+use yewchat::commands::{CommandContext, CommandOutput, Commands};
+use yewchat::utils::command::{parse_command, Command};
+
+#[test]
+fn commands_are_a_slash_and_a_name() {
+    assert_eq!(
+        parse_command("  /roll 2 d6 "),
+        Some(Command {
+            name: "roll",
+            rest: "2 d6",
+            args: vec!["2", "d6"],
+        })
+    );
+    assert_eq!(parse_command("/me").unwrap().args, Vec::<&str>::new());
+    assert_eq!(parse_command("/usr/bin is a path"), None);
+    assert_eq!(parse_command("/ hello"), None);
+    assert_eq!(parse_command("hello /roll"), None);
+}
+
+#[test]
+fn registered_commands_run_instead_of_sending() {
+    let commands = Commands::new()
+        .with_command("shrug", |c: &Command, _: &CommandContext| {
+            CommandOutput::Send(format!("{} ¯\\_(ツ)_/¯", c.rest).trim().to_string())
+        })
+        .with_command("whoami", |_: &Command, cx: &CommandContext| {
+            CommandOutput::Local(format!("You are {}", cx.me))
+        });
+    let cx = CommandContext { me: "alice" };
+
+    assert_eq!(
+        commands.run("/SHRUG oh well", &cx),
+        Some(CommandOutput::Send("oh well ¯\\_(ツ)_/¯".into()))
+    );
+    assert_eq!(
+        commands.run("/whoami", &cx),
+        Some(CommandOutput::Local("You are alice".into()))
+    );
+    // Unknown commands and plain text are left to the server.
+    assert_eq!(commands.run("/nick bob", &cx), None);
+    assert_eq!(commands.run("whoami", &cx), None);
+}
@@ -0,0 +1,6 @@
+use yewchat::services::protocol::{MsgTypes, WebSocketMessage};
+
+/// A frame of type `message_type` carrying `data`.
+pub fn frame(message_type: MsgTypes, data: &str) -> WebSocketMessage {
+    WebSocketMessage::new(message_type, Some(data.into()))
+}
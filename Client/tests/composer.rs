@@ -0,0 +1,65 @@
+use yewchat::state::composer::{Composer, Submission};
+
+fn composer(text: &str) -> Composer {
+    Composer {
+        text: text.into(),
+        ..Composer::default()
+    }
+}
+
+#[test]
+fn submitting_takes_the_text_and_attachments() {
+    let mut composer = composer("here it is");
+    composer
+        .attachments
+        .push("https://example.com/shot.png".into());
+    assert_eq!(
+        composer.submit(),
+        Some(Submission::Message {
+            text: "here it is".into(),
+            attachments: vec!["https://example.com/shot.png".into()],
+        })
+    );
+    assert!(composer.text.is_empty());
+    assert!(composer.attachments.is_empty());
+
+    composer.text = "fixed".into();
+    composer.editing = Some("7".into());
+    assert_eq!(
+        composer.submit(),
+        Some(Submission::Edit {
+            id: "7".into(),
+            message: "fixed".into()
+        })
+    );
+    assert_eq!(composer.editing, None);
+    assert!(composer.text.is_empty());
+}
+
+#[test]
+fn blank_text_sends_nothing_unless_there_are_attachments() {
+    let mut blank = composer("  \n");
+    assert_eq!(blank.submit(), None);
+    assert_eq!(blank.text, "  \n");
+
+    blank.attachments.push("https://example.com/a.pdf".into());
+    assert!(matches!(blank.submit(), Some(Submission::Message { .. })));
+
+    // Clearing the text while editing ends the edit.
+    let mut editing = composer(" ");
+    editing.editing = Some("7".into());
+    editing.attachments.push("https://example.com/b.pdf".into());
+    assert_eq!(editing.submit(), None);
+    assert_eq!(editing.editing, None);
+    assert_eq!(editing.attachments.len(), 1);
+}
+
+#[test]
+fn quotes_go_on_their_own_line() {
+    let mut composer = composer("");
+    composer.append_quote("> hi\n");
+    assert_eq!(composer.text, "> hi\n");
+    composer.text.push_str("agreed");
+    composer.append_quote("> more\n");
+    assert_eq!(composer.text, "> hi\nagreed\n> more\n");
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::frame;
+use yewchat::services::codec::{self, Codec, JsonCodec, Payload};
+use yewchat::services::compression::{compress, decompress, Compressed, MARKER};
+use yewchat::services::protocol::{MsgTypes, WebSocketMessage};
+
+fn message(text: &str) -> WebSocketMessage {
+    frame(MsgTypes::Message, text)
+}
+
+// A pasted stack trace: the kind of large message compression is for.
+fn pasted_log() -> String {
+    (0..60)
+        .map(|i| {
+            format!(
+                "2024-03-0{} 12:{:02}:07 WARN  chat::socket: reconnecting after error \
+                 (attempt {}, backoff {}ms)\n",
+                i % 9 + 1,
+                i,
+                i % 5,
+                1000 << (i % 5)
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_arbitrary_bytes() {
+    let samples: Vec<Vec<u8>> = vec![
+        vec![],
+        b"a".to_vec(),
+        b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_vec(),
+        b"abcabcabcabXabcabcab".to_vec(),
+        pasted_log().into_bytes(),
+        (0..20_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect(),
+    ];
+    for sample in samples {
+        assert_eq!(decompress(&compress(&sample)).unwrap(), sample);
+    }
+}
+
+#[test]
+fn pasted_text_shrinks_by_more_than_half() {
+    let log = pasted_log();
+    let compressed = compress(log.as_bytes());
+    let saved = 1.0 - compressed.len() as f64 / log.len() as f64;
+    assert!(
+        saved > 0.5,
+        "{} -> {} bytes ({:.0}% saved)",
+        log.len(),
+        compressed.len(),
+        saved * 100.0
+    );
+}
+
+#[test]
+fn only_large_frames_are_compressed() {
+    let codec = Compressed::new(JsonCodec);
+    assert!(matches!(
+        codec.encode_payload(&message("hi")),
+        Ok(Payload::Text(_))
+    ));
+
+    let big = message(&pasted_log());
+    let payload = codec.encode_payload(&big).unwrap();
+    let bytes = match &payload {
+        Payload::Binary(bytes) => bytes,
+        Payload::Text(_) => panic!("large frame was not compressed"),
+    };
+    assert_eq!(bytes[0], MARKER);
+    assert!(bytes.len() < JsonCodec.encode(&big).unwrap().len() / 2);
+    let decoded = codec.decode_payload(&payload).unwrap();
+    assert_eq!(decoded.data, big.data);
+}
+
+#[test]
+fn uncompressed_frames_still_decode() {
+    let codec = Compressed::new(JsonCodec);
+    let raw = JsonCodec.encode(&message("hi")).unwrap();
+    assert!(codec.decode_payload(&Payload::Text(raw.clone())).is_ok());
+    assert!(codec
+        .decode_payload(&Payload::Binary(raw.into_bytes()))
+        .is_ok());
+}
+
+#[test]
+fn corrupt_frames_are_rejected() {
+    let mut compressed = compress(pasted_log().as_bytes());
+    assert!(decompress(&compressed[..compressed.len() / 2]).is_err());
+    // A back-reference in the very first token points before the data.
+    assert!(decompress(&[4, 0b1, 0x00, 0x10]).is_err());
+    compressed[0] ^= 0x01;
+    assert!(decompress(&compressed).is_err());
+    assert!(decompress(&[]).is_err());
+}
+
+#[test]
+fn the_configured_codec_compresses_unless_turned_off() {
+    let payload = codec::configured()
+        .encode_payload(&message(&pasted_log()))
+        .unwrap();
+    let compressed = !cfg!(any(feature = "line-protocol", feature = "no-compression"));
+    assert_eq!(matches!(payload, Payload::Binary(_)), compressed);
+}
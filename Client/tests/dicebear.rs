@@ -0,0 +1,41 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::MsgTypes;
+use yewchat::state::chat::ChatState;
+use yewchat::utils::dicebear::DicebearVersion;
+use yewchat::utils::url::encode_component;
+
+#[test]
+fn each_version_has_its_own_url_template() {
+    assert_eq!(
+        DicebearVersion::V6.avatar_url("alice"),
+        "https://avatars.dicebear.com/api/adventurer-neutral/alice.svg"
+    );
+    assert_eq!(
+        DicebearVersion::V7.avatar_url("alice"),
+        "https://api.dicebear.com/7.x/adventurer-neutral/svg?seed=alice"
+    );
+    assert_eq!(DicebearVersion::default(), DicebearVersion::V7);
+}
+
+#[test]
+fn seeds_are_percent_encoded() {
+    assert_eq!(encode_component("a b&c/é"), "a%20b%26c%2F%C3%A9");
+    assert_eq!(
+        DicebearVersion::V7.avatar_url("bo?x=1"),
+        "https://api.dicebear.com/7.x/adventurer-neutral/svg?seed=bo%3Fx%3D1"
+    );
+}
+
+#[test]
+fn the_roster_uses_the_configured_version() {
+    let mut state = ChatState::new("alice".into());
+    state.avatar_api = DicebearVersion::V6;
+    state.apply(frame(
+        MsgTypes::Users,
+        r#"[{"id":"bo","displayName":"Bo"},{"id":"cy","displayName":"Cy","avatar":"https://example.com/cy.png"}]"#,
+    ));
+    assert_eq!(state.users[0].avatar, DicebearVersion::V6.avatar_url("bo"));
+    assert_eq!(state.users[1].avatar, "https://example.com/cy.png");
+}
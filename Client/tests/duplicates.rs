@@ -0,0 +1,66 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::{MsgTypes, WebSocketMessage};
+use yewchat::state::chat::ChatState;
+
+fn message(id: u32, from: &str, text: &str, time: u32) -> WebSocketMessage {
+    frame(
+        MsgTypes::Message,
+        &format!(
+            r#"{{"id":"{}","from":"{}","message":"{}","time":{}}}"#,
+            id, from, text, time
+        ),
+    )
+}
+
+fn shown(state: &ChatState) -> Vec<(&str, usize)> {
+    state
+        .messages
+        .iter()
+        .map(|m| (m.id.as_deref().unwrap(), m.duplicates.len() + 1))
+        .collect()
+}
+
+#[test]
+fn repeats_within_the_window_collapse() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(message(1, "bob", "hi", 0));
+    state.apply(message(2, "bob", "hi", 300));
+    state.apply(message(3, "bob", "hi", 5_000));
+    assert_eq!(shown(&state), [("1", 3)]);
+}
+
+#[test]
+fn repeats_outside_the_window_or_from_others_stay_separate() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(message(1, "bob", "hi", 0));
+    state.apply(message(2, "bob", "hi", 5_001));
+    state.apply(message(3, "cat", "hi", 5_002));
+    state.apply(message(4, "cat", "hi!", 5_003));
+    assert_eq!(shown(&state), [("1", 1), ("2", 1), ("3", 1), ("4", 1)]);
+}
+
+#[test]
+fn no_window_shows_every_copy() {
+    let mut state = ChatState::new("alice".into());
+    state.duplicate_window_ms = None;
+    state.apply(message(1, "bob", "hi", 0));
+    state.apply(message(2, "bob", "hi", 1));
+    assert_eq!(shown(&state), [("1", 1), ("2", 1)]);
+}
+
+#[test]
+fn deleting_one_copy_leaves_the_others() {
+    let mut state = ChatState::new("alice".into());
+    for id in 1..=3 {
+        state.apply(message(id, "bob", "hi", id));
+    }
+    state.apply(frame(MsgTypes::Delete, "2"));
+    assert_eq!(shown(&state), [("1", 2), ("2", 1)]);
+    assert!(state.message("2").unwrap().deleted);
+
+    state.apply(frame(MsgTypes::Delete, "1"));
+    assert_eq!(shown(&state), [("1", 1), ("3", 1), ("2", 1)]);
+    assert!(!state.message("3").unwrap().deleted);
+}
@@ -0,0 +1,32 @@
+use yewchat::utils::emoji::{move_selection, search, GridMove, EMOJI};
+
+#[test]
+fn search_matches_shortcodes_ignoring_case_and_colons() {
+    assert_eq!(search(""), EMOJI);
+    assert_eq!(search(":TADA:"), [("tada", "🎉")]);
+    let hearts: Vec<_> = search("heart").into_iter().map(|(code, _)| code).collect();
+    assert_eq!(hearts, ["heart_eyes", "heart", "broken_heart"]);
+    assert!(search("no such emoji").is_empty());
+}
+
+#[test]
+fn arrows_move_through_the_grid() {
+    // Ten cells, four to a row: 0-3, 4-7, 8-9.
+    assert_eq!(move_selection(1, 10, 4, GridMove::Right), 2);
+    assert_eq!(move_selection(3, 10, 4, GridMove::Right), 4);
+    assert_eq!(move_selection(9, 10, 4, GridMove::Right), 0);
+    assert_eq!(move_selection(0, 10, 4, GridMove::Left), 9);
+    assert_eq!(move_selection(5, 10, 4, GridMove::Up), 1);
+    assert_eq!(move_selection(1, 10, 4, GridMove::Up), 1);
+    assert_eq!(move_selection(1, 10, 4, GridMove::Down), 5);
+    assert_eq!(move_selection(7, 10, 4, GridMove::Down), 9);
+    assert_eq!(move_selection(8, 10, 4, GridMove::Down), 8);
+}
+
+#[test]
+fn an_empty_or_shrunk_grid_keeps_the_selection_in_range() {
+    assert_eq!(move_selection(3, 0, 4, GridMove::Down), 0);
+    assert_eq!(move_selection(12, 3, 4, GridMove::Up), 2);
+    assert_eq!(GridMove::from_key("ArrowDown"), Some(GridMove::Down));
+    assert_eq!(GridMove::from_key("Enter"), None);
+}
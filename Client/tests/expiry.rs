@@ -0,0 +1,39 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::MsgTypes;
+use yewchat::state::chat::ChatState;
+
+#[test]
+fn messages_disappear_once_their_time_is_up() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"1","from":"bob","message":"psst","expiresAt":2000}"#,
+    ));
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"2","from":"bob","message":"hello"}"#,
+    ));
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"3","from":"carol","message":"what?","threadId":"1"}"#,
+    ));
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"4","from":"carol","message":"soon","threadId":"2","expiresAt":5000}"#,
+    ));
+    assert_eq!(state.next_expiry(), Some(2000.0));
+
+    assert!(!state.expire(1999.0));
+    assert!(state.expire(2000.0));
+    assert!(state.message("1").is_none());
+    // Replies go with their root.
+    assert!(state.replies("1").is_empty());
+    assert_eq!(state.next_expiry(), Some(5000.0));
+
+    assert!(state.expire(6000.0));
+    assert!(state.message("2").is_some());
+    assert!(state.replies("2").is_empty());
+    assert_eq!(state.next_expiry(), None);
+}
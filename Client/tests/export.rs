@@ -0,0 +1,68 @@
+use yewchat::services::protocol::{Category, MessageData};
+use yewchat::utils::export::{iso_timestamp, to_json, to_transcript};
+
+fn message(id: &str, from: &str, text: &str, time: f64) -> MessageData {
+    MessageData {
+        id: Some(id.into()),
+        from: from.into(),
+        message: text.into(),
+        time: Some(time),
+        ..MessageData::default()
+    }
+}
+
+fn history() -> Vec<MessageData> {
+    let mut edited = message("2", "bob", "two\nlines", 1_700_000_060_000.0);
+    edited.edited = true;
+    edited.display_name = Some("Bob".into());
+    let deleted = MessageData {
+        deleted: true,
+        ..message("3", "alice", "", 1_700_000_120_000.0)
+    };
+    let notice = MessageData {
+        category: Category::System,
+        ..message("4", "", "bob joined", 1_700_000_180_000.0)
+    };
+    vec![
+        message("1", "alice", "hi", 1_700_000_000_000.0),
+        edited,
+        deleted,
+        notice,
+    ]
+}
+
+#[test]
+fn timestamps_are_utc_iso_8601() {
+    assert_eq!(iso_timestamp(0.0), "1970-01-01T00:00:00.000Z");
+    assert_eq!(
+        iso_timestamp(1_700_000_000_123.0),
+        "2023-11-14T22:13:20.123Z"
+    );
+    assert_eq!(iso_timestamp(951_782_400_000.0), "2000-02-29T00:00:00.000Z");
+}
+
+#[test]
+fn json_export_keeps_room_sender_time_and_text() {
+    let json: serde_json::Value =
+        serde_json::from_str(&to_json("Lobby", &history(), 0.0).unwrap()).unwrap();
+    assert_eq!(json["room"], "Lobby");
+    assert_eq!(json["exportedAt"], "1970-01-01T00:00:00.000Z");
+    let messages = json["messages"].as_array().unwrap();
+    // Deleted messages and notices aren't part of the record.
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0]["from"], "alice");
+    assert_eq!(messages[0]["displayName"], "alice");
+    assert_eq!(messages[0]["time"], "2023-11-14T22:13:20.000Z");
+    assert_eq!(messages[1]["displayName"], "Bob");
+    assert_eq!(messages[1]["message"], "two\nlines");
+    assert_eq!(messages[1]["edited"], true);
+}
+
+#[test]
+fn transcript_has_one_entry_per_message() {
+    let transcript = to_transcript("Lobby", &history(), |time| format!("t{}", time / 1e12));
+    assert_eq!(
+        transcript,
+        "Lobby\n\n[t1.7] alice: hi\n[t1.70000006] Bob: two\n    lines (edited)\n"
+    );
+}
@@ -0,0 +1,37 @@
+use yewchat::utils::format::{insert_at, wrap_selection, Style};
+
+#[test]
+fn selection_is_wrapped_and_stays_selected() {
+    assert_eq!(
+        wrap_selection("make this loud", 5, 9, Style::Bold),
+        ("make **this** loud".to_string(), 7, 11)
+    );
+    // Backwards selections are wrapped the same way.
+    assert_eq!(
+        wrap_selection("run ls now", 6, 4, Style::Code),
+        ("run `ls` now".to_string(), 5, 7)
+    );
+}
+
+#[test]
+fn empty_selection_puts_the_cursor_between_delimiters() {
+    assert_eq!(
+        wrap_selection("hi ", 3, 3, Style::Italic),
+        ("hi **".to_string(), 4, 4)
+    );
+}
+
+#[test]
+fn offsets_are_utf16_units() {
+    // "🎉" is two UTF-16 units and four bytes.
+    assert_eq!(
+        wrap_selection("🎉 yay", 3, 6, Style::Bold),
+        ("🎉 **yay**".to_string(), 5, 8)
+    );
+}
+
+#[test]
+fn insertions_replace_the_selection_and_move_the_cursor_past_them() {
+    assert_eq!(insert_at("hi !", 3, 3, "🎉"), ("hi 🎉!".to_string(), 5));
+    assert_eq!(insert_at("so sad", 3, 6, "😢"), ("so 😢".to_string(), 5));
+}
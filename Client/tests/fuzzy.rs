@@ -0,0 +1,35 @@
+use yewchat::utils::fuzzy::{best_score, rank, score};
+use yewchat::utils::tokenize::pending_mention;
+
+#[test]
+fn queries_match_as_subsequences_ignoring_case() {
+    assert!(score("ab", "Alice Brown").is_some());
+    assert!(score("ALB", "alice brown").is_some());
+    assert!(score("ba", "Alice Brown").is_none());
+    assert!(score("alicex", "Alice").is_none());
+    assert_eq!(score("", "anyone"), Some(0));
+}
+
+#[test]
+fn word_starts_and_runs_rank_first() {
+    let names = ["Gabe Abbott", "Alice Brown", "abigail", "Bob"];
+    assert_eq!(
+        rank(names, |name| score("ab", name)),
+        ["abigail", "Alice Brown", "Gabe Abbott"]
+    );
+    // Ties keep the roster order.
+    assert_eq!(
+        rank(["bob", "rob"], |name| score("ob", name)),
+        ["bob", "rob"]
+    );
+    assert_eq!(best_score("al", &["Alice", "xyz"]), score("al", "Alice"));
+}
+
+#[test]
+fn only_a_trailing_mention_is_pending() {
+    assert_eq!(pending_mention("thanks @bo"), Some("bo"));
+    assert_eq!(pending_mention("@"), Some(""));
+    assert_eq!(pending_mention("@bob "), None);
+    assert_eq!(pending_mention("mail me@"), None);
+    assert_eq!(pending_mention("@bob!"), None);
+}
@@ -0,0 +1,31 @@
+use yewchat::utils::latency::Latency;
+
+#[test]
+fn round_trips_are_measured_per_ping() {
+    let mut latency = Latency::default();
+    let first = latency.ping(1_000.0);
+    let second = latency.ping(1_010.0);
+    assert_eq!(latency.pong(second, 1_050.0), Some(40.0));
+    assert_eq!(latency.pong(first, 1_060.0), Some(60.0));
+    assert_eq!(latency.average(), Some(50.0));
+}
+
+#[test]
+fn the_average_only_covers_recent_samples() {
+    let mut latency = Latency::default();
+    for rtt in [500.0, 10.0, 10.0, 10.0, 10.0, 10.0] {
+        let id = latency.ping(0.0);
+        latency.pong(id, rtt);
+    }
+    assert_eq!(latency.average(), Some(10.0));
+}
+
+#[test]
+fn unknown_and_abandoned_pongs_are_ignored() {
+    let mut latency = Latency::default();
+    assert_eq!(latency.pong(7, 100.0), None);
+    let id = latency.ping(0.0);
+    latency.reset_pending();
+    assert_eq!(latency.pong(id, 100.0), None);
+    assert_eq!(latency.average(), None);
+}
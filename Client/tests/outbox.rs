@@ -0,0 +1,103 @@
+mod common;
+
+use common::frame;
+use serde_json::json;
+use yewchat::services::protocol::{MsgTypes, WebSocketMessage};
+use yewchat::state::chat::ChatState;
+use yewchat::state::outbox::{Outbox, MAX_RESENDS};
+
+const TIMEOUT: f64 = 10_000.0;
+
+fn message(text: &str) -> WebSocketMessage {
+    frame(MsgTypes::Message, text)
+}
+
+#[test]
+fn tracked_messages_get_unique_client_ids() {
+    let mut outbox = Outbox::new("s1".into());
+    let first = outbox.track(message("a"), 0.0);
+    let second = outbox.track(message("b"), 0.0);
+    assert_eq!(first.client_id.as_deref(), Some("s1-1"));
+    assert_eq!(second.client_id.as_deref(), Some("s1-2"));
+    assert_eq!(
+        serde_json::to_value(&first).unwrap()["clientId"],
+        json!("s1-1")
+    );
+
+    assert!(outbox.confirm("s1-2"));
+    assert!(!outbox.confirm("s1-2"));
+    assert_eq!(outbox.len(), 1);
+    assert!(outbox.confirm_oldest());
+    assert!(outbox.is_empty());
+}
+
+#[test]
+fn overdue_messages_are_resent_once_then_fail() {
+    let mut outbox = Outbox::new("s".into());
+    outbox.track(message("hello"), 0.0);
+    assert!(outbox.expire(TIMEOUT - 1.0, TIMEOUT).resend.is_empty());
+    assert_eq!(outbox.next_deadline(TIMEOUT), Some(TIMEOUT));
+
+    let mut now = TIMEOUT;
+    for _ in 0..MAX_RESENDS {
+        let expired = outbox.expire(now, TIMEOUT);
+        assert_eq!(expired.resend.len(), 1);
+        assert_eq!(expired.resend[0].client_id.as_deref(), Some("s-1"));
+        assert_eq!(expired.failed, 0);
+        now += TIMEOUT;
+    }
+    let expired = outbox.expire(now, TIMEOUT);
+    assert!(expired.resend.is_empty());
+    assert_eq!(expired.failed, 1);
+    assert!(outbox.is_empty());
+    assert_eq!(outbox.next_deadline(TIMEOUT), None);
+}
+
+#[test]
+fn postponing_restarts_the_wait() {
+    let mut outbox = Outbox::new("s".into());
+    outbox.track(message("hello"), 0.0);
+    outbox.postpone(8_000.0);
+    assert!(outbox.expire(TIMEOUT, TIMEOUT).resend.is_empty());
+    assert_eq!(outbox.next_deadline(TIMEOUT), Some(18_000.0));
+}
+
+#[test]
+fn a_second_echo_of_a_resent_message_is_ignored() {
+    let mut state = ChatState::new("alice".into());
+    let echo = WebSocketMessage {
+        data: Some(r#"{"id":"7","from":"alice","message":"hi","clientId":"s-1"}"#.into()),
+        ..message("")
+    };
+    assert!(state.apply(echo.clone()));
+    assert_eq!(state.messages[0].client_id.as_deref(), Some("s-1"));
+    assert!(!state.apply(echo));
+    assert_eq!(state.messages.len(), 1);
+}
+
+#[test]
+fn sending_the_same_text_twice_confirms_both_sends() {
+    let mut state = ChatState::new("alice".into());
+    let mut outbox = Outbox::new("s".into());
+    outbox.track(message("hi"), 0.0);
+    outbox.track(message("hi"), 0.0);
+    let echo = |id: u32, client_id: &str| {
+        frame(
+            MsgTypes::Message,
+            &format!(
+                r#"{{"id":"{}","from":"alice","message":"hi","time":1000,"clientId":"{}"}}"#,
+                id, client_id
+            ),
+        )
+    };
+    for (id, client_id) in [(1, "s-1"), (2, "s-2")] {
+        let entry = state.apply_entry(echo(id, client_id)).unwrap();
+        assert_eq!(entry.client_id.as_deref(), Some(client_id));
+        assert!(outbox.confirm(client_id));
+    }
+    // The second copy was collapsed into the first...
+    assert_eq!(state.messages.len(), 1);
+    assert_eq!(state.messages[0].duplicates, ["2"]);
+    // ...but both sends went through.
+    assert!(outbox.is_empty());
+}
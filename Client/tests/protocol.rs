@@ -0,0 +1,309 @@
+mod common;
+
+use common::frame;
+use serde_json::{json, Value};
+use yewchat::services::protocol::{
+    Category, ErrorData, MessageData, MessageEdit, MsgTypes, WebSocketMessage,
+};
+use yewchat::state::chat::ChatState;
+
+const SERVER_FRAMES: &str = include_str!("fixtures/server_frames.jsonl");
+
+fn to_json(msg: &WebSocketMessage) -> Value {
+    serde_json::to_value(msg).unwrap()
+}
+
+#[test]
+fn message_types_serialize_lowercase() {
+    let cases = [
+        (MsgTypes::Users, "users"),
+        (MsgTypes::Register, "register"),
+        (MsgTypes::Message, "message"),
+        (MsgTypes::Typing, "typing"),
+        (MsgTypes::Edit, "edit"),
+        (MsgTypes::Delete, "delete"),
+        (MsgTypes::Profile, "profile"),
+        (MsgTypes::History, "history"),
+        (MsgTypes::Notice, "notice"),
+        (MsgTypes::Announcement, "announcement"),
+        (MsgTypes::Error, "error"),
+        (MsgTypes::Pin, "pin"),
+        (MsgTypes::Unpin, "unpin"),
+        (MsgTypes::PinOrder, "pinorder"),
+        (MsgTypes::Read, "read"),
+    ];
+    for (message_type, name) in cases {
+        assert_eq!(serde_json::to_value(message_type).unwrap(), json!(name));
+    }
+}
+
+#[test]
+fn outgoing_frames_use_camel_case_fields() {
+    assert_eq!(
+        to_json(&frame(MsgTypes::Register, "alice")),
+        json!({ "messageType": "register", "dataArray": null, "data": "alice" })
+    );
+    assert_eq!(
+        to_json(&frame(MsgTypes::Message, "hello")),
+        json!({ "messageType": "message", "dataArray": null, "data": "hello" })
+    );
+    assert_eq!(
+        to_json(&frame(MsgTypes::Typing, "true")),
+        json!({ "messageType": "typing", "dataArray": null, "data": "true" })
+    );
+    assert_eq!(
+        to_json(&WebSocketMessage::new(MsgTypes::History, None)),
+        json!({ "messageType": "history", "dataArray": null, "data": null })
+    );
+}
+
+#[test]
+fn edit_frames_nest_their_payload_as_a_string() {
+    let edit = MessageEdit {
+        id: "7".into(),
+        message: "fixed".into(),
+    };
+    let data = serde_json::to_string(&edit).unwrap();
+    assert_eq!(
+        to_json(&frame(MsgTypes::Edit, &data)),
+        json!({
+            "messageType": "edit",
+            "dataArray": null,
+            "data": "{\"id\":\"7\",\"message\":\"fixed\"}",
+        })
+    );
+}
+
+#[test]
+fn message_data_fills_in_optional_fields() {
+    let full: MessageData = serde_json::from_str(
+        r#"{"id":"2","from":"bob","displayName":"Bob","message":"hi","time":5.0,"edited":true}"#,
+    )
+    .unwrap();
+    assert_eq!(full.id.as_deref(), Some("2"));
+    assert_eq!(full.display_name.as_deref(), Some("Bob"));
+    assert_eq!(full.time, Some(5.0));
+    assert!(full.edited);
+    assert!(!full.deleted);
+
+    // Frames from the original server carry only the sender and text.
+    let minimal: MessageData = serde_json::from_str(r#"{"from":"bob","message":"hi"}"#).unwrap();
+    assert_eq!(minimal.id, None);
+    assert_eq!(minimal.display_name, None);
+    assert_eq!(minimal.time, None);
+}
+
+#[test]
+fn server_fixture_replays_into_state() {
+    let mut state = ChatState::new("alice".into());
+    for line in SERVER_FRAMES.lines() {
+        let msg: WebSocketMessage = serde_json::from_str(line).unwrap();
+        assert!(state.apply(msg), "frame had no effect: {}", line);
+    }
+
+    assert_eq!(state.users.len(), 2);
+    assert_eq!(state.display_name("bob"), "Bob");
+    assert_eq!(
+        state.user("bob").unwrap().avatar,
+        "https://example.com/bob.png"
+    );
+    assert!(state.history_complete);
+    // Bob's message clears his typing indicator.
+    assert!(state.typing_users.is_empty());
+
+    let texts = state
+        .messages
+        .iter()
+        .map(|m| m.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["earlier", "hello there", ""]);
+    assert!(state.message("2").unwrap().edited);
+    assert!(state.message("3").unwrap().deleted);
+}
+
+#[test]
+fn id_only_roster_is_still_understood() {
+    let mut state = ChatState::new("alice".into());
+    let msg: WebSocketMessage =
+        serde_json::from_str(r#"{"messageType":"users","dataArray":["alice","bob"]}"#).unwrap();
+    assert!(state.apply(msg));
+    assert_eq!(state.display_name("bob"), "bob");
+}
+
+#[test]
+fn malformed_frames_fail_to_parse() {
+    let bad = [
+        "",
+        "not json",
+        r#"{"data":"hi"}"#,
+        r#"{"messageType":"shout","data":"hi"}"#,
+        r#"{"messageType":"Message","data":"hi"}"#,
+    ];
+    for raw in bad {
+        assert!(
+            serde_json::from_str::<WebSocketMessage>(raw).is_err(),
+            "accepted {:?}",
+            raw
+        );
+    }
+}
+
+#[test]
+fn malformed_payloads_are_ignored() {
+    let mut state = ChatState::new("alice".into());
+    let bad = [
+        WebSocketMessage::new(MsgTypes::Message, None),
+        frame(MsgTypes::Message, "hello"),
+        frame(MsgTypes::Message, r#"{"message":"no sender"}"#),
+        frame(MsgTypes::Users, "[{\"id\":1}]"),
+        frame(MsgTypes::Typing, "true"),
+        frame(MsgTypes::Edit, r#"{"id":"1"}"#),
+        WebSocketMessage::new(MsgTypes::Delete, None),
+        frame(MsgTypes::History, "[]"),
+    ];
+    for msg in bad {
+        assert!(!state.apply(msg));
+    }
+    assert!(state.messages.is_empty());
+    assert!(state.users.is_empty());
+    assert!(state.typing_users.is_empty());
+    assert!(!state.history_complete);
+}
+
+#[test]
+fn error_frames_describe_themselves() {
+    let known: ErrorData = serde_json::from_str(r#"{"code":"rate_limited"}"#).unwrap();
+    assert!(known.describe().contains("too quickly"));
+
+    let custom: ErrorData =
+        serde_json::from_str(r#"{"code":"banned","message":"You were banned."}"#).unwrap();
+    assert_eq!(custom.describe(), "You were banned.");
+
+    let bare: ErrorData = serde_json::from_str(r#"{"code":"teapot"}"#).unwrap();
+    assert_eq!(bare.describe(), "The server rejected the request (teapot).");
+}
+
+#[test]
+fn only_errors_about_a_send_reject_it() {
+    let error = |json: &str| serde_json::from_str::<ErrorData>(json).unwrap();
+    let named = error(r#"{"code":"not_registered","clientId":"s-3"}"#);
+    assert_eq!(named.client_id.as_deref(), Some("s-3"));
+    assert!(named.rejects_send());
+    assert!(error(r#"{"code":"rate_limited"}"#).rejects_send());
+    // Failed edits, deletes and pins leave pending messages alone.
+    assert!(!error(r#"{"code":"not_owner"}"#).rejects_send());
+    assert!(!error(r#"{"code":"not_registered"}"#).rejects_send());
+    assert!(!error(r#"{"code":"unknown_thread"}"#).rejects_send());
+}
+
+#[test]
+fn announcements_show_without_a_sender() {
+    let mut state = ChatState::new("alice".into());
+    state.away = true;
+    assert!(state.apply(frame(MsgTypes::Announcement, "Restarting at 22:00 UTC")));
+    let announcement = &state.messages[0];
+    assert_eq!(announcement.category, Category::Announcement);
+    assert_eq!(announcement.message, "Restarting at 22:00 UTC");
+    assert!(announcement.from.is_empty());
+    assert_eq!(state.unread_count(), 0);
+}
+
+#[test]
+fn error_frames_leave_the_message_list_alone() {
+    let mut state = ChatState::new("alice".into());
+    assert!(!state.apply(frame(MsgTypes::Error, r#"{"code":"not_owner"}"#)));
+    assert!(state.messages.is_empty());
+}
+
+#[test]
+fn pins_follow_edits_deletes_and_unpins() {
+    let mut state = ChatState::new("alice".into());
+    let hello = r#"{"id":"1","from":"bob","message":"hello"}"#;
+    let later = r#"{"id":"2","from":"bob","message":"later"}"#;
+    state.apply(frame(MsgTypes::Message, hello));
+    assert!(state.apply(frame(MsgTypes::Pin, hello)));
+    assert!(!state.apply(frame(MsgTypes::Pin, hello)));
+    assert!(state.apply(frame(MsgTypes::Pin, later)));
+    assert!(state.is_pinned("1") && state.is_pinned("2"));
+
+    state.apply(frame(MsgTypes::Edit, r#"{"id":"1","message":"hi"}"#));
+    assert_eq!(state.pinned[0].message, "hi");
+    assert!(state.pinned[0].edited);
+
+    assert!(state.apply(frame(MsgTypes::Delete, "1")));
+    assert!(state.apply(frame(MsgTypes::Unpin, "2")));
+    assert!(state.pinned.is_empty());
+    assert!(!state.apply(frame(MsgTypes::Unpin, "2")));
+}
+
+#[test]
+fn pins_can_be_reordered() {
+    let mut state = ChatState::new("alice".into());
+    for id in ["1", "2", "3"] {
+        let pin = format!(r#"{{"id":"{}","from":"bob","message":"hi"}}"#, id);
+        state.apply(frame(MsgTypes::Pin, &pin));
+    }
+    assert!(state.move_pin("3", 0));
+    assert_eq!(state.pin_order(), ["3", "1", "2"]);
+    assert!(!state.move_pin("2", 7));
+    assert!(!state.move_pin("4", 0));
+
+    // A shared order wins; pins it doesn't know about go last.
+    let order = WebSocketMessage {
+        data_array: Some(vec!["2".into(), "1".into()]),
+        ..WebSocketMessage::new(MsgTypes::PinOrder, None)
+    };
+    assert!(state.apply(order.clone()));
+    assert_eq!(state.pin_order(), ["2", "1", "3"]);
+    assert!(!state.apply(order));
+}
+
+#[test]
+fn message_data_serializes_in_the_server_shape() {
+    let raw = r#"{"id":"2","from":"bob","displayName":"Bob","message":"hi","time":5.0}"#;
+    let message: MessageData = serde_json::from_str(raw).unwrap();
+    assert_eq!(
+        serde_json::to_value(&message).unwrap(),
+        json!({
+            "id": "2",
+            "from": "bob",
+            "displayName": "Bob",
+            "message": "hi",
+            "time": 5.0,
+            "edited": false,
+            "deleted": false,
+        })
+    );
+}
+
+#[test]
+fn messages_carry_attachments_in_data_array() {
+    let outgoing = WebSocketMessage {
+        data_array: Some(vec!["https://example.com/shot.png".into()]),
+        ..frame(MsgTypes::Message, "here's the screenshot")
+    };
+    assert_eq!(
+        to_json(&outgoing),
+        json!({
+            "messageType": "message",
+            "dataArray": ["https://example.com/shot.png"],
+            "data": "here's the screenshot",
+        })
+    );
+
+    let raw =
+        r#"{"id":"3","from":"bob","message":"see","attachments":["https://example.com/a.pdf"]}"#;
+    let message: MessageData = serde_json::from_str(raw).unwrap();
+    assert_eq!(message.attachments, ["https://example.com/a.pdf"]);
+    assert_eq!(
+        serde_json::to_value(&message).unwrap()["attachments"],
+        json!(["https://example.com/a.pdf"])
+    );
+    // Messages without attachments keep the old shape.
+    assert!(
+        serde_json::from_str::<MessageData>(r#"{"from":"bob","message":"hi"}"#)
+            .unwrap()
+            .attachments
+            .is_empty()
+    );
+}
@@ -0,0 +1,38 @@
+use yewchat::utils::quote::{blocks, quote, Block};
+
+fn block(quoted: bool, text: &str) -> Block {
+    Block {
+        quoted,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn every_line_of_a_quote_is_marked() {
+    assert_eq!(quote("hello"), "> hello\n");
+    assert_eq!(quote("one\ntwo\n\nthree\n"), "> one\n> two\n> \n> three\n");
+}
+
+#[test]
+fn quoting_a_quote_nests_it() {
+    assert_eq!(quote("> earlier\nreply"), "> > earlier\n> reply\n");
+}
+
+#[test]
+fn quotes_split_into_blocks() {
+    assert_eq!(
+        blocks("> one\n> two\nmy reply\nmore"),
+        [block(true, "one\ntwo\n"), block(false, "my reply\nmore")]
+    );
+    assert_eq!(blocks(">tight\n>\nafter"), [block(true, "tight\n\n"), block(false, "after")]);
+    assert_eq!(blocks("no quotes > here"), [block(false, "no quotes > here")]);
+}
+
+#[test]
+fn a_quoted_reply_round_trips() {
+    let composed = quote("line one\nline two") + "sounds good";
+    assert_eq!(
+        blocks(&composed),
+        [block(true, "line one\nline two\n"), block(false, "sounds good")]
+    );
+}
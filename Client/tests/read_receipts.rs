@@ -0,0 +1,51 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::MsgTypes;
+use yewchat::state::chat::ChatState;
+
+fn room() -> ChatState {
+    let mut state = ChatState::new("alice".into());
+    state.apply(frame(
+        MsgTypes::Users,
+        r#"[{"id":"alice","displayName":"Alice"},{"id":"bob","displayName":"Bob"},{"id":"carol","displayName":"Carol"}]"#,
+    ));
+    for (id, from) in [("1", "bob"), ("2", "carol"), ("3", "bob")] {
+        state.apply(frame(
+            MsgTypes::Message,
+            &format!(r#"{{"id":"{}","from":"{}","message":"hi"}}"#, id, from),
+        ));
+    }
+    state
+}
+
+#[test]
+fn readers_gather_under_the_newest_message_they_have_seen() {
+    let mut state = room();
+    assert!(state.apply(frame(MsgTypes::Read, r#"{"from":"bob","id":"3"}"#)));
+    assert!(state.apply(frame(MsgTypes::Read, r#"{"from":"carol","id":"1"}"#)));
+    assert!(!state.apply(frame(MsgTypes::Read, r#"{"from":"carol","id":"1"}"#)));
+    // Our own receipt, echoed back, isn't shown.
+    assert!(!state.apply(frame(MsgTypes::Read, r#"{"from":"alice","id":"3"}"#)));
+
+    let seen = state.seen_by();
+    let names = |id: &str| seen[id].iter().map(|u| u.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names("3"), ["Bob"]);
+    assert_eq!(names("1"), ["Carol"]);
+    assert!(!seen.contains_key("2"));
+}
+
+#[test]
+fn our_receipt_stops_before_unread_messages() {
+    let mut state = room();
+    assert_eq!(state.newest_read(), Some("3"));
+
+    state.away = true;
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"4","from":"bob","message":"still there?"}"#,
+    ));
+    assert_eq!(state.newest_read(), Some("3"));
+    state.mark_all_read();
+    assert_eq!(state.newest_read(), Some("4"));
+}
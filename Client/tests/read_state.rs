@@ -0,0 +1,79 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::{MsgTypes, WebSocketMessage};
+use yewchat::state::chat::ChatState;
+
+fn message(id: u32, from: &str) -> WebSocketMessage {
+    frame(
+        MsgTypes::Message,
+        &format!(
+            r#"{{"id":"{}","from":"{}","message":"message {}","time":{}}}"#,
+            id, from, id, id
+        ),
+    )
+}
+
+fn history(ids: &[u32]) -> WebSocketMessage {
+    let messages = ids
+        .iter()
+        .map(|id| format!(r#"{{"id":"{}","from":"bob","message":"old","time":{}}}"#, id, id))
+        .collect::<Vec<_>>();
+    frame(
+        MsgTypes::History,
+        &format!(r#"{{"messages":[{}],"more":false}}"#, messages.join(",")),
+    )
+}
+
+#[test]
+fn messages_arriving_while_away_are_unread() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(message(1, "bob"));
+    state.away = true;
+    state.apply(message(2, "bob"));
+    state.apply(message(3, "alice"));
+    state.apply(message(4, "bob"));
+
+    assert_eq!(state.first_unread(), Some(1));
+    assert_eq!(state.unread_count(), 2);
+    assert_eq!(state.last_read, Some(3.0));
+}
+
+#[test]
+fn partial_reads_move_the_divider_down() {
+    let mut state = ChatState::new("alice".into());
+    state.away = true;
+    for id in 1..=3 {
+        state.apply(message(id, "bob"));
+    }
+
+    assert!(state.mark_read_through(1));
+    assert_eq!(state.first_unread(), Some(2));
+    assert_eq!(state.last_read, Some(2.0));
+    assert!(!state.mark_read_through(0));
+
+    assert!(state.mark_all_read());
+    assert_eq!(state.first_unread(), None);
+    assert_eq!(state.last_read, Some(3.0));
+    assert!(!state.mark_all_read());
+}
+
+#[test]
+fn history_newer_than_the_saved_marker_is_unread() {
+    let mut state = ChatState::new("alice".into());
+    state.last_read = Some(2.0);
+    state.apply(history(&[1, 2, 3, 4]));
+
+    assert_eq!(state.first_unread(), Some(2));
+    assert_eq!(state.unread_count(), 2);
+    assert_eq!(state.last_read, Some(2.0));
+}
+
+#[test]
+fn without_a_saved_marker_history_is_read() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(history(&[1, 2]));
+
+    assert_eq!(state.first_unread(), None);
+    assert_eq!(state.last_read, Some(2.0));
+}
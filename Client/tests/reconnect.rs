@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use yewchat::services::websocket::{
+    jittered, retry_delay, ExponentialBackoff, FixedDelay, Jitter, Jittered,
+    ReconnectStrategy,
+};
+
+fn delays(strategy: &dyn ReconnectStrategy, attempts: u32) -> Vec<u128> {
+    (1..=attempts).map(|a| strategy.next_delay(a).as_millis()).collect()
+}
+
+#[test]
+fn retries_stop_after_the_maximum() {
+    let backoff = ExponentialBackoff::default();
+    assert_eq!(retry_delay(&backoff, 3, Some(3)), Some(4_000));
+    assert_eq!(retry_delay(&backoff, 4, Some(3)), None);
+    assert_eq!(retry_delay(&backoff, 1, Some(0)), None);
+    let fixed = FixedDelay(Duration::from_millis(2_500));
+    assert_eq!(retry_delay(&fixed, 3, Some(3)), Some(2_500));
+    assert_eq!(retry_delay(&fixed, 4, Some(3)), None);
+}
+
+#[test]
+fn unlimited_retries_never_stop() {
+    assert_eq!(retry_delay(&ExponentialBackoff::default(), u32::MAX, None), Some(30_000));
+}
+
+#[test]
+fn jitter_stays_within_its_fraction() {
+    assert_eq!(jittered(10_000, 0.2, 0.0), 8_000);
+    assert_eq!(jittered(10_000, 0.2, 0.5), 10_000);
+    assert_eq!(jittered(10_000, 0.0, 0.9), 10_000);
+    // Out-of-range fractions are clamped rather than going negative.
+    assert_eq!(jittered(10_000, 3.0, 0.0), 0);
+
+    let mut rng = Jitter::new(42);
+    let delays = (0..1_000)
+        .map(|_| jittered(4_000, 0.2, rng.next_unit()))
+        .collect::<Vec<_>>();
+    assert!(delays.iter().all(|&d| (3_200..=4_800).contains(&d)));
+    // And they actually spread out.
+    assert!(delays.iter().any(|&d| d < 3_600) && delays.iter().any(|&d| d > 4_400));
+}
+
+#[test]
+fn fixed_delays_never_change() {
+    let fixed = FixedDelay(Duration::from_millis(2_500));
+    assert_eq!(delays(&fixed, 4), [2_500, 2_500, 2_500, 2_500]);
+}
+
+#[test]
+fn exponential_backoff_doubles_from_its_base_to_its_max() {
+    assert_eq!(
+        delays(&ExponentialBackoff::default(), 7),
+        [1_000, 2_000, 4_000, 8_000, 16_000, 30_000, 30_000]
+    );
+    let fast = ExponentialBackoff {
+        base: Duration::from_millis(100),
+        max: Duration::from_millis(500),
+    };
+    assert_eq!(delays(&fast, 5), [100, 200, 400, 500, 500]);
+    // Huge attempt numbers saturate instead of overflowing.
+    assert_eq!(fast.next_delay(u32::MAX), Duration::from_millis(500));
+}
+
+#[test]
+fn jittered_strategies_spread_around_the_inner_delays() {
+    let none = Jittered::new(ExponentialBackoff::default(), 0.0, 7);
+    assert_eq!(delays(&none, 3), [1_000, 2_000, 4_000]);
+
+    let fixed = Jittered::new(FixedDelay(Duration::from_secs(10)), 0.2, 42);
+    let spread = delays(&fixed, 1_000);
+    assert!(spread.iter().all(|&d| (8_000..=12_000).contains(&d)));
+    assert!(spread.iter().any(|&d| d < 9_000) && spread.iter().any(|&d| d > 11_000));
+    // The same seed gives the same sequence.
+    let again = Jittered::new(FixedDelay(Duration::from_secs(10)), 0.2, 42);
+    assert_eq!(delays(&again, 1_000), spread);
+}
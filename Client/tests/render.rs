@@ -0,0 +1,127 @@
+use yew::prelude::*;
+use yewchat::render::{
+    plain_text, Broadcasts, Content, ContentRenderer, Images, Links, Mentions, MessageRenderer,
+    RenderContext, Rendered, Renderers,
+};
+use yewchat::services::protocol::RosterEntry;
+use yewchat::state::chat::UserProfile;
+use yewchat::utils::dicebear::DicebearVersion;
+use yewchat::utils::tokenize::Segment;
+
+const CX: RenderContext = RenderContext { me: "alice", roster: None };
+
+#[test]
+fn each_renderer_only_claims_its_own_segments() {
+    let link = Segment::Link {
+        label: "docs",
+        url: "https://yew.rs",
+    };
+    assert!(Links.render(&link, &CX).is_some());
+    assert!(Links.render(&Segment::Text("hi"), &CX).is_none());
+    assert!(Mentions.render(&Segment::Mention("bob"), &CX).is_some());
+    assert!(Mentions.render(&Segment::Broadcast("here"), &CX).is_none());
+    assert!(Broadcasts.render(&Segment::Broadcast("here"), &CX).is_some());
+    assert_eq!(
+        Images.render(&Segment::Image("https://x.test/a.png"), &CX),
+        Some(Rendered::Image("https://x.test/a.png".into()))
+    );
+}
+
+#[test]
+fn mentions_of_me_render_differently() {
+    let me = Mentions.render(&Segment::Mention("Alice"), &CX);
+    let other = Mentions.render(&Segment::Mention("bob"), &CX);
+    assert_ne!(me, other);
+}
+
+#[test]
+fn defaults_leave_plain_text_alone() {
+    let renderers = Renderers::default();
+    assert!(renderers.render(&Segment::Text("hi"), &CX).is_none());
+    assert!(renderers.render(&Segment::Mention("bob"), &CX).is_some());
+}
+
+#[test]
+fn disabled_renderers_fall_back_to_the_typed_text() {
+    let renderers = Renderers::new(vec![Box::new(Links)]);
+    let image = Segment::Image("https://x.test/a.png");
+    assert!(renderers.render(&image, &CX).is_none());
+    assert_eq!(plain_text(&image), "https://x.test/a.png");
+    assert_eq!(plain_text(&Segment::Mention("bob")), "@bob");
+    let link = Segment::Link {
+        label: "docs",
+        url: "https://yew.rs",
+    };
+    assert_eq!(plain_text(&link), "[docs](https://yew.rs)");
+}
+
+struct Json;
+
+impl ContentRenderer for Json {
+    fn render(&self, body: &str, _cx: &RenderContext) -> Html {
+        html! { <pre>{body.to_string()}</pre> }
+    }
+}
+
+#[test]
+fn content_types_pick_how_a_message_is_shown() {
+    let renderers = Renderers::default();
+    assert!(matches!(renderers.content(None), Content::Markdown));
+    assert!(matches!(
+        renderers.content(Some("text/markdown")),
+        Content::Markdown
+    ));
+    assert!(matches!(
+        renderers.content(Some("Text/Plain; charset=utf-8")),
+        Content::Plain
+    ));
+    assert!(matches!(
+        renderers.content(Some("image/webp")),
+        Content::Image
+    ));
+    assert!(matches!(
+        renderers.content(Some("application/ox")),
+        Content::Unsupported
+    ));
+}
+
+#[test]
+fn registered_content_renderers_come_first() {
+    let renderers = Renderers::default()
+        .with_content_type("application/json", Box::new(Json))
+        .with_content_type("image/*", Box::new(Json));
+    assert!(matches!(
+        renderers.content(Some("application/json")),
+        Content::Custom(_)
+    ));
+    assert!(matches!(
+        renderers.content(Some("image/png")),
+        Content::Custom(_)
+    ));
+    assert!(matches!(
+        renderers.content(Some("application/jsonl")),
+        Content::Unsupported
+    ));
+}
+
+#[test]
+fn mentions_of_absent_users_are_muted() {
+    let roster = [UserProfile::new(
+        RosterEntry {
+            id: "bob".into(),
+            display_name: "Bob".into(),
+            avatar: None,
+            away: false,
+        },
+        DicebearVersion::default(),
+    )];
+    let cx = RenderContext { me: "alice", roster: Some(&roster) };
+    assert!(cx.is_present("Bob") && cx.is_present("alice"));
+    assert!(!cx.is_present("carol"));
+    let present = Mentions.render(&Segment::Mention("bob"), &cx);
+    let absent = Mentions.render(&Segment::Mention("carol"), &cx);
+    assert!(absent.is_some());
+    assert_ne!(present, absent);
+    // Until the roster loads, nobody is assumed to have left.
+    assert!(CX.is_present("carol"));
+}
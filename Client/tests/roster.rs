@@ -0,0 +1,153 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::{MsgTypes, WebSocketMessage};
+use yewchat::state::chat::{ChatState, Presence};
+
+fn roster(json: &str) -> WebSocketMessage {
+    frame(MsgTypes::Users, json)
+}
+
+fn names(state: &ChatState) -> Vec<(&str, &str)> {
+    state
+        .users
+        .iter()
+        .map(|u| (u.id.as_str(), u.name.as_str()))
+        .collect()
+}
+
+#[test]
+fn repeated_ids_keep_the_first_entry() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(
+        r#"[{"id":"sam","displayName":"Sam"},{"id":"sam","displayName":"Impostor"}]"#,
+    ));
+    assert_eq!(names(&state), [("sam", "Sam")]);
+}
+
+#[test]
+fn shared_display_names_get_their_id_appended() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(
+        r#"[{"id":"sam","displayName":"sam"},{"id":"sam2","displayName":"sam"},{"id":"bo","displayName":"Bo"}]"#,
+    ));
+    assert_eq!(
+        names(&state),
+        [("sam", "sam"), ("sam2", "sam (sam2)"), ("bo", "Bo")]
+    );
+    // Attribution goes by id, so each message still finds its own sender.
+    assert_eq!(state.display_name("sam2"), "sam (sam2)");
+    assert_eq!(state.display_name("sam"), "sam");
+}
+
+#[test]
+fn resending_a_disambiguated_roster_is_not_a_change() {
+    let mut state = ChatState::new("alice".into());
+    let json = r#"[{"id":"a","displayName":"Sam"},{"id":"b","displayName":"Sam"}]"#;
+    assert!(state.apply(roster(json)));
+    assert!(!state.apply(roster(json)));
+}
+
+#[test]
+fn unchanged_entries_survive_a_roster_update() {
+    let mut state = ChatState::new("alice".into());
+    let before = r#"[{"id":"bo","displayName":"Bo"},{"id":"cy","displayName":"Cy","avatar":"https://example.com/cy.png"}]"#;
+    state.apply(roster(before));
+    let avatars = state.users.iter().map(|u| u.avatar.as_ptr()).collect::<Vec<_>>();
+
+    let after = r#"[{"id":"bo","displayName":"Bo"},{"id":"cy","displayName":"Cyrus","avatar":"https://example.com/cy.png"}]"#;
+    assert!(state.apply(roster(after)));
+    assert_eq!(names(&state), [("bo", "Bo"), ("cy", "Cyrus")]);
+    // Same allocations, so the avatar URLs weren't rebuilt and the images
+    // aren't requested again.
+    let kept = state.users.iter().map(|u| u.avatar.as_ptr()).collect::<Vec<_>>();
+    assert_eq!(kept, avatars);
+    assert!(!state.apply(roster(after)));
+
+    // A new avatar does replace the old one.
+    state.apply(roster(
+        r#"[{"id":"bo","displayName":"Bo"},{"id":"cy","displayName":"Cyrus","avatar":"https://example.com/cy2.png"}]"#,
+    ));
+    assert_eq!(state.users[0].avatar.as_ptr(), avatars[0]);
+    assert_eq!(state.users[1].avatar, "https://example.com/cy2.png");
+}
+
+fn presence(state: &ChatState) -> Vec<(&str, Presence)> {
+    state
+        .roster_view()
+        .into_iter()
+        .map(|(u, p)| (u.id.as_str(), p))
+        .collect()
+}
+
+#[test]
+fn the_first_roster_does_not_animate() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(r#"[{"id":"alice","displayName":"alice"},{"id":"bob","displayName":"bob"}]"#));
+    assert_eq!(
+        presence(&state),
+        [("alice", Presence::Present), ("bob", Presence::Present)]
+    );
+}
+
+#[test]
+fn joins_and_leaves_are_marked_until_settled() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(r#"[{"id":"alice","displayName":"alice"},{"id":"bob","displayName":"bob"},{"id":"cat","displayName":"cat"}]"#));
+    state.apply(roster(r#"[{"id":"alice","displayName":"alice"},{"id":"cat","displayName":"cat"},{"id":"dan","displayName":"dan"}]"#));
+    assert_eq!(
+        presence(&state),
+        [
+            ("alice", Presence::Present),
+            ("bob", Presence::Leaving),
+            ("cat", Presence::Present),
+            ("dan", Presence::Joined),
+        ]
+    );
+
+    assert!(state.settle_presence());
+    assert_eq!(names(&state), [("alice", "alice"), ("cat", "cat"), ("dan", "dan")]);
+    assert!(state.roster_view().iter().all(|(_, p)| *p == Presence::Present));
+    assert!(!state.settle_presence());
+}
+
+#[test]
+fn rejoining_cancels_the_leave() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(r#"[{"id":"alice","displayName":"alice"},{"id":"bob","displayName":"bob"}]"#));
+    state.apply(roster(r#"[{"id":"alice","displayName":"alice"}]"#));
+    state.apply(roster(r#"[{"id":"alice","displayName":"alice"},{"id":"bob","displayName":"bob"}]"#));
+    assert_eq!(
+        presence(&state),
+        [("alice", Presence::Present), ("bob", Presence::Joined)]
+    );
+}
+
+#[test]
+fn going_away_and_back_updates_the_roster() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(r#"[{"id":"bo","displayName":"Bo"}]"#));
+    assert!(!state.users[0].away);
+    assert!(state.apply(roster(
+        r#"[{"id":"bo","displayName":"Bo","away":true}]"#
+    )));
+    assert!(state.users[0].away);
+    assert!(state.apply(roster(r#"[{"id":"bo","displayName":"Bo"}]"#)));
+    assert!(!state.users[0].away);
+}
+
+fn page(state: &ChatState, filter: &str, limit: Option<usize>) -> (Vec<String>, usize) {
+    let (page, more) = state.roster_page(filter, limit);
+    (page.iter().map(|(u, _)| u.id.clone()).collect(), more)
+}
+
+#[test]
+fn the_sidebar_lists_active_people_first_up_to_the_limit() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(roster(
+        r#"[{"id":"al","displayName":"Al","away":true},{"id":"bo","displayName":"Bo"},{"id":"cy","displayName":"Cy"}]"#,
+    ));
+    assert_eq!(page(&state, "", Some(2)), (vec!["bo".into(), "cy".into()], 1));
+    assert_eq!(page(&state, "", None).0, ["bo", "cy", "al"]);
+    assert_eq!(page(&state, "a", Some(2)), (vec!["al".into()], 0));
+}
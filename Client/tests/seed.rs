@@ -0,0 +1,58 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::{MessageData, MsgTypes, RosterEntry};
+use yewchat::state::chat::{ChatState, Seed};
+
+fn message(id: &str, from: &str, text: &str) -> MessageData {
+    MessageData {
+        id: Some(id.into()),
+        from: from.into(),
+        message: text.into(),
+        ..Default::default()
+    }
+}
+
+fn seed() -> Seed {
+    let reply = MessageData {
+        thread_id: Some("1".into()),
+        ..message("2", "alice", "tea?")
+    };
+    Seed {
+        users: ["alice", "bob"]
+            .iter()
+            .map(|&id| RosterEntry {
+                id: id.into(),
+                display_name: id.into(),
+                avatar: None,
+                away: false,
+            })
+            .collect(),
+        messages: vec![message("1", "bob", "morning"), reply, message("3", "alice", "hi bob")],
+        more: true,
+    }
+}
+
+#[test]
+fn a_seed_loads_like_a_roster_and_history_page() {
+    let mut state = ChatState::new("alice".into());
+    state.seed(seed());
+    assert_eq!(state.users.len(), 2);
+    // Nobody has just joined in a seeded room.
+    assert!(state.joined.is_empty());
+    assert_eq!(state.messages.len(), 2);
+    assert_eq!(state.replies("1").len(), 1);
+    assert!(!state.history_complete);
+}
+
+#[test]
+fn live_frames_apply_on_top_of_a_seed() {
+    let mut state = ChatState::new("alice".into());
+    state.seed(seed());
+    let live = |data: &str| frame(MsgTypes::Message, data);
+    assert!(state.apply(live(r#"{"id":"4","from":"bob","message":"hey"}"#)));
+    // The same message again, as a reconnect might deliver it, is dropped.
+    assert!(!state.apply(live(r#"{"id":"3","from":"alice","message":"hi bob"}"#)));
+    let ids = state.messages.iter().map(|m| m.id.as_deref().unwrap()).collect::<Vec<_>>();
+    assert_eq!(ids, ["1", "3", "4"]);
+}
@@ -0,0 +1,33 @@
+use yewchat::services::websocket::upgrade_for_page;
+
+#[test]
+fn insecure_sockets_are_upgraded_on_https_pages() {
+    assert_eq!(
+        upgrade_for_page("ws://chat.example.com:8080/ws", "https:"),
+        Some("wss://chat.example.com:8080/ws".to_string())
+    );
+    assert_eq!(
+        upgrade_for_page("ws://10.0.0.5:8080", "https:"),
+        Some("wss://10.0.0.5:8080".to_string())
+    );
+}
+
+#[test]
+fn loopback_sockets_are_allowed_from_https_pages() {
+    for url in [
+        "ws://127.0.0.1:8080",
+        "ws://localhost:8080",
+        "ws://LOCALHOST",
+        "ws://chat.localhost/ws",
+        "ws://[::1]:8080",
+    ] {
+        assert_eq!(upgrade_for_page(url, "https:"), None, "{}", url);
+    }
+}
+
+#[test]
+fn other_combinations_are_left_alone() {
+    assert_eq!(upgrade_for_page("ws://chat.example.com", "http:"), None);
+    assert_eq!(upgrade_for_page("wss://chat.example.com", "https:"), None);
+    assert_eq!(upgrade_for_page("wss://chat.example.com", "http:"), None);
+}
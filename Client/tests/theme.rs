@@ -0,0 +1,58 @@
+use yewchat::services::theme::Theme;
+
+#[test]
+fn default_theme_sets_every_variable() {
+    let css = Theme::default().css_variables();
+    for name in [
+        "--yc-primary:",
+        "--yc-primary-hover:",
+        "--yc-background:",
+        "--yc-surface:",
+        "--yc-input:",
+        "--yc-bubble:",
+        "--yc-bubble-text:",
+    ] {
+        assert!(css.contains(name), "{} missing from {}", name, css);
+    }
+    assert!(css.starts_with("--yc-primary: #2563eb;"));
+}
+
+#[test]
+fn values_cannot_break_out_of_their_property() {
+    let theme = Theme {
+        primary: "red; display: none".into(),
+        ..Theme::default()
+    };
+    assert!(theme.css_variables().starts_with("--yc-primary: red display: none;"));
+}
+
+// WCAG 2 contrast ratio between two `#rrggbb` colours.
+fn contrast(a: &str, b: &str) -> f64 {
+    let luminance = |hex: &str| {
+        let channel = |i: usize| {
+            let c = u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(1) + 0.7152 * channel(3) + 0.0722 * channel(5)
+    };
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+#[test]
+fn high_contrast_theme_meets_wcag_aaa() {
+    let theme = Theme::high_contrast();
+    for (fg, bg) in [
+        (&theme.bubble_text, &theme.bubble),
+        (&theme.primary, &theme.background),
+        (&theme.primary, &theme.surface),
+        (&"#ffffff".to_string(), &theme.primary),
+        (&"#ffffff".to_string(), &theme.primary_hover),
+    ] {
+        assert!(contrast(fg, bg) >= 7.0, "{} on {} is only {:.1}:1", fg, bg, contrast(fg, bg));
+    }
+}
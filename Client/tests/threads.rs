@@ -0,0 +1,68 @@
+mod common;
+
+use common::frame;
+use serde_json::json;
+use yewchat::services::protocol::{MessageReply, MsgTypes};
+use yewchat::state::chat::ChatState;
+
+#[test]
+fn replies_name_their_thread() {
+    let reply = MessageReply {
+        thread_id: "1".into(),
+        message: "agreed".into(),
+    };
+    assert_eq!(
+        serde_json::to_value(&reply).unwrap(),
+        json!({"threadId": "1", "message": "agreed"})
+    );
+    assert_eq!(
+        serde_json::to_value(MsgTypes::Reply).unwrap(),
+        json!("reply")
+    );
+}
+
+#[test]
+fn replies_gather_under_their_root() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"1","from":"bob","message":"lunch?"}"#,
+    ));
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"2","from":"carol","message":"yes","threadId":"1"}"#,
+    ));
+    assert_eq!(state.messages.len(), 1);
+    assert_eq!(state.replies("1").len(), 1);
+    assert_eq!(state.latest().unwrap().message, "yes");
+    assert!(state.replies("2").is_empty());
+
+    state.apply(frame(MsgTypes::Edit, r#"{"id":"2","message":"yes!"}"#));
+    assert_eq!(state.message("2").unwrap().message, "yes!");
+    state.apply(frame(MsgTypes::Delete, "2"));
+    assert!(state.replies("1")[0].deleted);
+}
+
+#[test]
+fn older_replies_load_before_newer_ones() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(frame(
+        MsgTypes::Message,
+        r#"{"id":"5","from":"bob","message":"late","threadId":"1"}"#,
+    ));
+    let page = json!({
+        "messages": [
+            {"id": "1", "from": "bob", "message": "root"},
+            {"id": "3", "from": "carol", "message": "early", "threadId": "1"},
+        ],
+        "more": false,
+    });
+    state.apply(frame(MsgTypes::History, &page.to_string()));
+    assert_eq!(state.messages.len(), 1);
+    let replies: Vec<_> = state
+        .replies("1")
+        .iter()
+        .map(|m| m.message.as_str())
+        .collect();
+    assert_eq!(replies, ["early", "late"]);
+}
@@ -0,0 +1,76 @@
+use yewchat::utils::time::{clock_time, day_breaks, format_clock, format_countdown, format_date, normalize_timestamp, TimeFormat};
+
+#[test]
+fn clock_times_follow_the_hour_cycle() {
+    assert_eq!(format_clock(0, 5, TimeFormat::H24), "00:05");
+    assert_eq!(format_clock(0, 5, TimeFormat::H12), "12:05 AM");
+    assert_eq!(format_clock(13, 30, TimeFormat::H12), "1:30 PM");
+}
+
+// 2023-11-14 00:00 UTC.
+const MIDNIGHT: f64 = 1_699_920_000_000.0;
+const HOUR_MS: f64 = 3_600_000.0;
+
+#[test]
+fn timestamps_format_in_both_hour_cycles() {
+    let at = |epoch_ms: f64, format| {
+        let (hours, minutes) = clock_time(epoch_ms, 0);
+        format_clock(hours, minutes, format)
+    };
+    assert_eq!(at(MIDNIGHT, TimeFormat::H24), "00:00");
+    assert_eq!(at(MIDNIGHT, TimeFormat::H12), "12:00 AM");
+    assert_eq!(at(MIDNIGHT + 12.0 * HOUR_MS, TimeFormat::H24), "12:00");
+    assert_eq!(at(MIDNIGHT + 12.0 * HOUR_MS, TimeFormat::H12), "12:00 PM");
+    // 22:13:20; the seconds are dropped, not rounded.
+    assert_eq!(at(1_700_000_000_000.0, TimeFormat::H24), "22:13");
+    assert_eq!(at(1_700_000_000_000.0, TimeFormat::H12), "10:13 PM");
+}
+
+#[test]
+fn clock_times_apply_the_utc_offset() {
+    assert_eq!(clock_time(MIDNIGHT, 330), (5, 30));
+    // West of UTC it is still the previous evening.
+    assert_eq!(clock_time(MIDNIGHT, -300), (19, 0));
+    assert_eq!(clock_time(MIDNIGHT - 60_000.0, 0), (23, 59));
+}
+
+#[test]
+fn dates_are_zero_padded() {
+    assert_eq!(format_date(2024, 3, 5), "2024-03-05");
+    assert_eq!(format_date(2024, 12, 31), "2024-12-31");
+}
+
+#[test]
+fn countdowns_show_the_two_largest_units() {
+    assert_eq!(format_countdown(45_000.0), "45s");
+    assert_eq!(format_countdown(252_000.0), "4m 12s");
+    assert_eq!(format_countdown(3_600_000.0), "1h");
+    assert_eq!(format_countdown(183_600_000.0), "2d 3h");
+    // Partial seconds round up, and overdue shows as done.
+    assert_eq!(format_countdown(100.0), "1s");
+    assert_eq!(format_countdown(-5.0), "0s");
+}
+
+#[test]
+fn future_and_impossible_timestamps_are_normalized() {
+    assert_eq!(normalize_timestamp(1_000.0, 2_000.0), Some(1_000.0));
+    assert_eq!(normalize_timestamp(5_000.0, 2_000.0), Some(2_000.0));
+    assert_eq!(normalize_timestamp(-1.0, 2_000.0), None);
+    assert_eq!(normalize_timestamp(f64::NAN, 2_000.0), None);
+}
+
+#[test]
+fn each_day_is_announced_once() {
+    assert_eq!(
+        day_breaks([Some(1), Some(1), None, Some(2), Some(2)]),
+        [Some(1), None, None, Some(2), None]
+    );
+}
+
+#[test]
+fn out_of_order_days_stay_under_the_current_separator() {
+    assert_eq!(
+        day_breaks([Some(2), Some(1), Some(2), Some(3), Some(1), Some(3)]),
+        [Some(2), None, None, Some(3), None, None]
+    );
+}
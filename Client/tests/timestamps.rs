@@ -0,0 +1,50 @@
+mod common;
+
+use common::frame;
+use yewchat::services::protocol::{MessageData, MsgTypes};
+use yewchat::state::chat::{ChatState, Seed};
+use yewchat::utils::time::now_ms;
+
+const DAY_MS: f64 = 86_400_000.0;
+
+#[test]
+fn future_timestamps_are_clamped_to_now() {
+    let mut state = ChatState::new("alice".into());
+    let future = now_ms() + DAY_MS;
+    state.apply(frame(
+        MsgTypes::Message,
+        &format!(r#"{{"id":"1","from":"bob","message":"hi","time":{}}}"#, future),
+    ));
+    let time = state.messages[0].time.unwrap();
+    assert!(time < future && time <= now_ms());
+    // A fast sender's clock mustn't mark everything before "tomorrow" as read.
+    assert!(state.last_read.unwrap() <= now_ms());
+}
+
+#[test]
+fn impossible_timestamps_are_dropped() {
+    let mut state = ChatState::new("alice".into());
+    state.apply(frame(MsgTypes::Message, r#"{"id":"1","from":"bob","message":"hi","time":-5}"#));
+    assert_eq!(state.messages[0].time, None);
+}
+
+#[test]
+fn history_timestamps_are_normalized_and_kept_in_order() {
+    let mut state = ChatState::new("alice".into());
+    let now = now_ms();
+    let message = |id: &str, time: f64| MessageData {
+        id: Some(id.into()),
+        from: "bob".into(),
+        time: Some(time),
+        ..Default::default()
+    };
+    state.seed(Seed {
+        messages: vec![message("1", now - DAY_MS), message("2", now - 2.0 * DAY_MS), message("3", now + DAY_MS)],
+        ..Default::default()
+    });
+    // Out-of-order times keep the server's order; only the future one changes.
+    let ids = state.messages.iter().map(|m| m.id.as_deref().unwrap()).collect::<Vec<_>>();
+    assert_eq!(ids, ["1", "2", "3"]);
+    assert_eq!(state.messages[1].time, Some(now - 2.0 * DAY_MS));
+    assert!(state.messages[2].time.unwrap() <= now_ms());
+}
@@ -0,0 +1,188 @@
+use yewchat::utils::tokenize::{mentions, mentions_everyone, tokenize, Segment};
+
+#[test]
+fn plain_text_is_one_segment() {
+    assert_eq!(tokenize("hello there"), [Segment::Text("hello there")]);
+    assert!(tokenize("").is_empty());
+}
+
+#[test]
+fn a_lone_image_is_one_segment() {
+    assert_eq!(
+        tokenize("https://example.com/cat.gif"),
+        [Segment::Image("https://example.com/cat.gif")]
+    );
+}
+
+#[test]
+fn images_are_split_out_of_surrounding_text() {
+    let message = "look: https://a.example/1.png and\nhttps://b.example/2.JPG?size=large done";
+    let segments = tokenize(message);
+    assert_eq!(
+        segments,
+        [
+            Segment::Text("look: "),
+            Segment::Image("https://a.example/1.png"),
+            Segment::Text(" and\n"),
+            Segment::Image("https://b.example/2.JPG?size=large"),
+            Segment::Text(" done"),
+        ]
+    );
+    let rebuilt: String = segments
+        .iter()
+        .map(|s| match s {
+            Segment::Text(t) | Segment::Image(t) => *t,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(rebuilt, message);
+}
+
+#[test]
+fn non_image_urls_stay_text() {
+    assert_eq!(
+        tokenize("see https://example.com/page or ftp://x/y.png"),
+        [Segment::Text(
+            "see https://example.com/page or ftp://x/y.png"
+        )]
+    );
+}
+
+#[test]
+fn markdown_links_become_link_segments() {
+    assert_eq!(
+        tokenize("read [the docs](https://yew.rs/docs) first"),
+        [
+            Segment::Text("read "),
+            Segment::Link {
+                label: "the docs",
+                url: "https://yew.rs/docs"
+            },
+            Segment::Text(" first"),
+        ]
+    );
+}
+
+#[test]
+fn links_to_other_schemes_stay_text() {
+    for message in [
+        "[click](javascript:alert(1))",
+        "[click](JavaScript:alert(1))",
+        "[click](data:text/html;base64,PHNjcmlwdD4=)",
+        "[click](//evil.example)",
+        "[click](https://evil.example/a b)",
+    ] {
+        assert_eq!(tokenize(message), [Segment::Text(message)], "{}", message);
+    }
+}
+
+#[test]
+fn unbalanced_brackets_stay_text() {
+    for message in [
+        "[oops",
+        "oops]",
+        "[no url]",
+        "[label](https://example.com",
+        "[label] (https://example.com)",
+        "[](https://example.com)",
+        "[two\nlines](https://example.com)",
+    ] {
+        assert_eq!(tokenize(message), [Segment::Text(message)], "{}", message);
+    }
+}
+
+#[test]
+fn the_innermost_bracket_opens_the_link() {
+    assert_eq!(
+        tokenize("[[x](https://a.example)"),
+        [
+            Segment::Text("["),
+            Segment::Link {
+                label: "x",
+                url: "https://a.example"
+            },
+        ]
+    );
+}
+
+#[test]
+fn images_next_to_links_are_still_found() {
+    assert_eq!(
+        tokenize("[src](https://a.example) https://a.example/x.png"),
+        [
+            Segment::Link {
+                label: "src",
+                url: "https://a.example"
+            },
+            Segment::Text(" "),
+            Segment::Image("https://a.example/x.png"),
+        ]
+    );
+}
+
+#[test]
+fn mentions_are_split_out_of_words() {
+    assert_eq!(
+        tokenize("hey @bob, ask @alice.smith."),
+        [
+            Segment::Text("hey "),
+            Segment::Mention("bob"),
+            Segment::Text(", ask "),
+            Segment::Mention("alice.smith"),
+            Segment::Text("."),
+        ]
+    );
+    // Not at the start of a word, or no name at all.
+    assert_eq!(
+        tokenize("mail me@example.com @ @!"),
+        [Segment::Text("mail me@example.com @ @!")]
+    );
+}
+
+#[test]
+fn mentions_match_names_case_insensitively() {
+    assert!(mentions("thanks @Alice!", "alice"));
+    assert!(!mentions("thanks @alicia", "alice"));
+    assert!(!mentions("[@alice](https://example.com)", "alice"));
+}
+
+#[test]
+fn everyone_and_here_are_broadcasts() {
+    assert_eq!(
+        tokenize("@everyone standup, @Here too"),
+        [
+            Segment::Broadcast("everyone"),
+            Segment::Text(" standup, "),
+            Segment::Broadcast("Here"),
+            Segment::Text(" too"),
+        ]
+    );
+    assert!(mentions_everyone("heads up @here"));
+    assert!(!mentions_everyone("heads up @hereford"));
+    // A broadcast is not a mention of a user called "everyone".
+    assert!(!mentions("@everyone", "everyone"));
+}
+
+#[test]
+fn markdown_emphasis_is_split_out() {
+    assert_eq!(
+        tokenize("so **very** *much* `code`"),
+        [
+            Segment::Text("so "),
+            Segment::Bold("very"),
+            Segment::Text(" "),
+            Segment::Italic("much"),
+            Segment::Text(" "),
+            Segment::Code("code"),
+        ]
+    );
+}
+
+#[test]
+fn stray_asterisks_stay_text() {
+    assert_eq!(tokenize("2 * 3 * 4"), [Segment::Text("2 * 3 * 4")]);
+    assert_eq!(tokenize("**"), [Segment::Text("**")]);
+    assert_eq!(tokenize("*a\nb*"), [Segment::Text("*a\nb*")]);
+    // Code keeps inner spacing and markdown verbatim.
+    assert_eq!(tokenize("` *x* `"), [Segment::Code(" *x* ")]);
+}
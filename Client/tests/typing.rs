@@ -0,0 +1,57 @@
+use yewchat::utils::typing::{typing_summary, TypingSignal};
+
+#[test]
+fn nobody_typing_shows_nothing() {
+    assert_eq!(typing_summary(&[]), None);
+}
+
+#[test]
+fn one_or_two_typers_are_named() {
+    assert_eq!(typing_summary(&["Alice"]).unwrap(), "Alice is typing…");
+    assert_eq!(
+        typing_summary(&["Alice", "Bob"]).unwrap(),
+        "Alice and Bob are typing…"
+    );
+}
+
+#[test]
+fn more_typers_are_counted() {
+    assert_eq!(
+        typing_summary(&["Alice", "Bob", "Cy"]).unwrap(),
+        "Alice, Bob and 1 other are typing…"
+    );
+    assert_eq!(
+        typing_summary(&["Alice", "Bob", "Cy", "Di", "Ed"]).unwrap(),
+        "Alice, Bob and 3 others are typing…"
+    );
+}
+
+#[test]
+fn typing_is_only_sent_when_it_changes() {
+    let mut signal = TypingSignal::default();
+    assert_eq!(signal.set_typing(true), Some(true));
+    assert_eq!(signal.set_typing(true), None);
+    assert_eq!(signal.set_typing(false), Some(false));
+}
+
+#[test]
+fn going_away_while_typing_stops_the_indicator() {
+    let mut signal = TypingSignal::default();
+    signal.set_typing(true);
+    assert_eq!(signal.set_away(true), Some(false));
+    assert!(!signal.is_shown());
+    // Coming back doesn't claim we're typing again until we are.
+    assert_eq!(signal.set_away(false), None);
+    assert_eq!(signal.set_typing(true), Some(true));
+}
+
+#[test]
+fn nothing_is_sent_while_away() {
+    let mut signal = TypingSignal::default();
+    assert_eq!(signal.set_away(true), None);
+    assert_eq!(signal.set_typing(true), None);
+    assert_eq!(signal.set_typing(false), None);
+    // Typing before the activity that ends the away state isn't replayed.
+    signal.set_typing(true);
+    assert_eq!(signal.set_away(false), None);
+}
@@ -0,0 +1,62 @@
+use yewchat::utils::url::{
+    anchored_message, download_name, file_name, message_anchor, with_query_param,
+};
+
+#[test]
+fn anchors_round_trip_through_the_hash() {
+    let hash = format!("#{}", message_anchor("42"));
+    assert_eq!(hash, "#msg-42");
+    assert_eq!(anchored_message(&hash), Some("42"));
+}
+
+#[test]
+fn other_hashes_link_to_nothing() {
+    for hash in ["", "#", "#msg-", "#top", "msg-42"] {
+        assert_eq!(anchored_message(hash), None, "{:?}", hash);
+    }
+}
+
+#[test]
+fn file_names_come_from_the_last_path_segment() {
+    assert_eq!(
+        file_name("https://example.com/docs/spec.pdf?v=2"),
+        "spec.pdf"
+    );
+    assert_eq!(file_name("https://example.com/files/"), "files");
+    assert_eq!(file_name("https://example.com"), "example.com");
+}
+
+#[test]
+fn download_names_are_decoded_and_safe_to_save() {
+    assert_eq!(
+        download_name("https://example.com/gifs/party%20cat.gif?w=200"),
+        "party cat.gif"
+    );
+    assert_eq!(
+        download_name("https://example.com/a%2Fb%3A.png"),
+        "a_b_.png"
+    );
+    assert_eq!(download_name("https://example.com/100%.png"), "100%.png");
+    assert_eq!(download_name("https://example.com/..."), "image");
+}
+
+#[test]
+fn query_params_join_any_existing_query() {
+    assert_eq!(
+        with_query_param("https://example.com/me.png", "retry", "1"),
+        "https://example.com/me.png?retry=1"
+    );
+    // A DiceBear URL keeps its seed, and with it the same avatar.
+    assert_eq!(
+        with_query_param(
+            "https://api.dicebear.com/7.x/adventurer/svg?seed=alice",
+            "retry",
+            "2"
+        ),
+        "https://api.dicebear.com/7.x/adventurer/svg?seed=alice&retry=2"
+    );
+    assert_eq!(
+        with_query_param("https://example.com/a.png#top", "retry", "1"),
+        "https://example.com/a.png?retry=1#top"
+    );
+}
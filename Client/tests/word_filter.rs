@@ -0,0 +1,27 @@
+use std::borrow::Cow;
+
+use yewchat::utils::word_filter::WordFilter;
+
+#[test]
+fn only_whole_words_are_masked() {
+    let filter = WordFilter::new(&["darn", "heck"]);
+    assert_eq!(filter.mask("darn it, what the heck!"), "**** it, what the ****!");
+    assert_eq!(filter.mask("darned hecks"), "darned hecks");
+    assert_eq!(filter.mask("(darn)"), "(****)");
+    // Nothing to mask leaves the text as it was, without copying it.
+    assert!(matches!(filter.mask("all good"), Cow::Borrowed("all good")));
+}
+
+#[test]
+fn matching_ignores_case() {
+    let filter = WordFilter::new(&[" Darn ", ""]);
+    assert_eq!(filter.mask("DARN and Darn and darn"), "**** and **** and ****");
+    assert_eq!(WordFilter::new(&["ÉCLAIR"]).mask("un éclair"), "un ******");
+}
+
+#[test]
+fn an_empty_list_filters_nothing() {
+    let filter = WordFilter::new::<&str>(&[]);
+    assert!(filter.is_empty());
+    assert_eq!(filter.mask("anything"), "anything");
+}
@@ -1,9 +1,18 @@
 import WebSocket, { WebSocketServer } from 'ws';
+import { createInterface } from 'readline';
 
 const PORT = process.env.PORT ? parseInt(process.env.PORT) : 8080;
+// Announced to each client as it joins, when set.
+const WELCOME_MESSAGE = process.env.WELCOME_MESSAGE;
 interface User {
     ws: WebSocket;
+    // Stable login username; `nick` is the name shown to others.
+    id: String;
     nick: String;
+    // Custom avatar URL; clients generate one from `id` when unset.
+    avatar?: String;
+    // Set while the client reports its user idle.
+    away?: boolean;
     isAlive: boolean;
 }
 
@@ -11,9 +20,26 @@ interface Message {
     messageType: String;
     data: String;
     dataArray: String[];
+    // Set by clients on messages and replies, which they may send twice.
+    clientId?: string;
+    // When a message should disappear, in milliseconds since the epoch.
+    expiresAt?: number;
+    // The name to show for a `register`ing user instead of their id.
+    displayName?: string;
 }
 
 let users: User[] = [];
+// Message id -> sender id, so only the author can edit or delete a message.
+const messageOwners = new Map<string, String>();
+let nextMessageId = 1;
+// Recent messages, oldest first, served to clients paging back through history.
+const history: any[] = [];
+const HISTORY_LIMIT = 500;
+const HISTORY_PAGE_SIZE = 30;
+// Pinned message id -> its history entry, kept after the entry leaves `history`.
+const pinned = new Map<string, any>();
+// User id -> the newest message id they have read, shown to others as "seen by".
+const readUpTo = new Map<String, string>();
 
 console.log(`Listening on port ${PORT}`);
 const wss = new WebSocketServer({ port: PORT });
@@ -21,25 +47,180 @@ const wss = new WebSocketServer({ port: PORT });
 wss.on('connection', (ws: WebSocket) => {
     console.log('ws connected');
 
-    ws.on('message', (data) => {
-        const raw_data = data.toString();
+    ws.on('message', (data, isBinary) => {
         try {
+            const bytes = data as Buffer;
+            const raw_data =
+                isBinary && bytes[0] === COMPRESSED_MARKER ? decompress(bytes.subarray(1)) : bytes.toString();
             const parsed_data: Message = JSON.parse(raw_data);
             switch (parsed_data.messageType) {
                 case 'register':
-                    users.push({ ws, nick: parsed_data.data, isAlive: true });
-                    broadcast(JSON.stringify({ messageType: 'users', dataArray: users.map((u) => u.nick) }));
+                    // Ids must be unique. A closed socket still holding the name is
+                    // a client reconnecting before the next cleanup, so it gives way.
+                    const holder = users.find((u) => u.id === parsed_data.data);
+                    if (holder && holder.ws !== ws && holder.ws.readyState === WebSocket.OPEN) {
+                        sendError(ws, 'username_taken');
+                        break;
+                    }
+                    users = users.filter((u) => u !== holder && u.ws !== ws);
+                    const nick = (parsed_data.displayName || '').trim() || parsed_data.data;
+                    users.push({ ws, id: parsed_data.data, nick, isAlive: true });
+                    broadcast(rosterFrame());
+                    pinned.forEach((entry) => ws.send(pinFrame(entry)));
+                    readUpTo.forEach((id, from) => ws.send(readFrame(from, id)));
+                    if (WELCOME_MESSAGE) {
+                        ws.send(announcementFrame(WELCOME_MESSAGE));
+                    }
                     break;
                 case 'message':
                     const sender = users.find((u) => u.ws === ws);
                     if (sender) {
+                        postMessage(sender, parsed_data, parsed_data.data);
+                    } else {
+                        sendError(ws, 'not_registered', parsed_data.clientId);
+                    }
+                    break;
+                case 'reply':
+                    const replier = users.find((u) => u.ws === ws);
+                    const reply = JSON.parse(parsed_data.data as string);
+                    // Replies to a reply join the thread it belongs to.
+                    const root = history.find((m) => m.id === reply.threadId && !m.deleted);
+                    if (!replier) {
+                        sendError(ws, 'not_registered', parsed_data.clientId);
+                    } else if (!root) {
+                        sendError(ws, 'unknown_thread', parsed_data.clientId);
+                    } else {
+                        postMessage(replier, parsed_data, reply.message, root.threadId || root.id);
+                    }
+                    break;
+                case 'edit':
+                    const editor = users.find((u) => u.ws === ws);
+                    const edit = JSON.parse(parsed_data.data as string);
+                    if (editor && messageOwners.get(edit.id) === editor.id) {
+                        const edited = history.find((m) => m.id === edit.id);
+                        if (edited) {
+                            edited.message = edit.message;
+                            edited.edited = true;
+                        }
+                        broadcast(
+                            JSON.stringify({
+                                messageType: 'edit',
+                                data: JSON.stringify({ id: edit.id, message: edit.message }),
+                            })
+                        );
+                    } else {
+                        sendError(ws, editor ? 'not_owner' : 'not_registered');
+                    }
+                    break;
+                case 'delete':
+                    const deleter = users.find((u) => u.ws === ws);
+                    const deleted_id = parsed_data.data as string;
+                    if (deleter && messageOwners.get(deleted_id) === deleter.id) {
+                        messageOwners.delete(deleted_id);
+                        pinned.delete(deleted_id);
+                        const removed = history.find((m) => m.id === deleted_id);
+                        if (removed) {
+                            removed.message = '';
+                            removed.deleted = true;
+                        }
+                        broadcast(JSON.stringify({ messageType: 'delete', data: deleted_id }));
+                    } else {
+                        sendError(ws, deleter ? 'not_owner' : 'not_registered');
+                    }
+                    break;
+                case 'profile':
+                    const profile_owner = users.find((u) => u.ws === ws);
+                    if (profile_owner) {
+                        profile_owner.avatar = parsed_data.data || undefined;
+                        broadcast(rosterFrame());
+                    }
+                    break;
+                case 'status':
+                    const status_owner = users.find((u) => u.ws === ws);
+                    const away = parsed_data.data === 'away';
+                    if (status_owner && !!status_owner.away !== away) {
+                        status_owner.away = away || undefined;
+                        broadcast(rosterFrame());
+                    }
+                    break;
+                case 'read':
+                    const reader = users.find((u) => u.ws === ws);
+                    const read_id = parsed_data.data as string;
+                    // Ids count up, so a receipt only ever moves forward.
+                    const read_before = Number(reader && readUpTo.get(reader.id)) || 0;
+                    if (reader && Number(read_id) > read_before && history.some((m) => m.id === read_id)) {
+                        readUpTo.set(reader.id, read_id);
+                        broadcast(readFrame(reader.id, read_id));
+                    }
+                    break;
+                case 'pin':
+                case 'unpin':
+                    // Anyone in the room may pin or unpin any message.
+                    const pin_id = parsed_data.data as string;
+                    if (!users.some((u) => u.ws === ws)) {
+                        sendError(ws, 'not_registered');
+                    } else if (parsed_data.messageType === 'unpin') {
+                        if (pinned.delete(pin_id)) {
+                            broadcast(JSON.stringify({ messageType: 'unpin', data: pin_id }));
+                        }
+                    } else if (!pinned.has(pin_id)) {
+                        const pinned_entry = history.find((m) => m.id === pin_id && !m.deleted);
+                        if (pinned_entry) {
+                            pinned.set(pin_id, pinned_entry);
+                            broadcast(pinFrame(pinned_entry));
+                        }
+                    }
+                    break;
+                case 'pinorder':
+                    // The full list of pins in their new order; anything else
+                    // crossed a pin or unpin, so the sender gets the current order.
+                    const order = parsed_data.dataArray || [];
+                    if (!users.some((u) => u.ws === ws)) {
+                        sendError(ws, 'not_registered');
+                    } else if (
+                        new Set(order).size === pinned.size &&
+                        order.length === pinned.size &&
+                        order.every((id) => pinned.has(id as string))
+                    ) {
+                        const reordered = order.map((id) => [id, pinned.get(id as string)]);
+                        pinned.clear();
+                        reordered.forEach(([id, entry]) => pinned.set(id, entry));
+                        broadcast(pinOrderFrame());
+                    } else {
+                        ws.send(pinOrderFrame());
+                    }
+                    break;
+                case 'history':
+                    // `data` is the oldest message id the client has; empty means the latest page.
+                    const before = parsed_data.data as string;
+                    let end = history.length;
+                    if (before) {
+                        end = history.findIndex((m) => m.id === before);
+                        if (end === -1) {
+                            end = 0;
+                        }
+                    }
+                    const start = Math.max(0, end - HISTORY_PAGE_SIZE);
+                    ws.send(
+                        JSON.stringify({
+                            messageType: 'history',
+                            data: JSON.stringify({ messages: history.slice(start, end), more: start > 0 }),
+                        })
+                    );
+                    break;
+                case 'ping':
+                    // Echoes the id back so the client can time the round trip.
+                    ws.send(JSON.stringify({ messageType: 'pong', data: parsed_data.data }));
+                    break;
+                case 'typing':
+                    const typist = users.find((u) => u.ws === ws);
+                    if (typist) {
                         broadcast(
                             JSON.stringify({
-                                messageType: 'message',
+                                messageType: 'typing',
                                 data: JSON.stringify({
-                                    from: sender.nick,
-                                    message: parsed_data.data,
-                                    time: Date.now(),
+                                    from: typist.id,
+                                    active: parsed_data.data === 'true',
                                 }),
                             })
                         );
@@ -56,10 +237,139 @@ const interval = setInterval(function ping() {
     const updated_users = users.filter((u) => current_clients.includes(u.ws));
     if (updated_users.length !== users.length) {
         users = updated_users;
-        broadcast(JSON.stringify({ messageType: 'users', dataArray: users.map((u) => u.nick) }));
+        broadcast(rosterFrame());
+    }
+    // Clients remove disappearing messages on their own; this just stops
+    // them being served again.
+    const now = Date.now();
+    for (let i = history.length - 1; i >= 0; i--) {
+        if (history[i].expiresAt <= now) {
+            messageOwners.delete(history[i].id);
+            pinned.delete(history[i].id);
+            history.splice(i, 1);
+        }
     }
 }, 5000);
 
+const rosterFrame = () =>
+    JSON.stringify({
+        messageType: 'users',
+        dataArray: users.map((u) => u.id),
+        data: JSON.stringify(users.map((u) => ({ id: u.id, displayName: u.nick, avatar: u.avatar, away: u.away }))),
+    });
+
+const readFrame = (from: String, id: string) => JSON.stringify({ messageType: 'read', data: JSON.stringify({ from, id }) });
+
+// Pins are sent and listed in `pinned` order.
+const pinOrderFrame = () => JSON.stringify({ messageType: 'pinorder', dataArray: Array.from(pinned.keys()) });
+
+const pinFrame = (entry: any) => JSON.stringify({ messageType: 'pin', data: JSON.stringify(entry) });
+
+// Operator notices carry their plain text in `data`.
+const announcementFrame = (text: string) => JSON.stringify({ messageType: 'announcement', data: text });
+
+// Each line typed into the server console is announced to every client.
+createInterface({ input: process.stdin }).on('line', (line) => {
+    const text = line.trim();
+    if (text) {
+        broadcast(announcementFrame(text));
+    }
+});
+
+// Stores and broadcasts a chat message; `threadId` makes it a reply.
+const postMessage = (sender: User, frame: Message, text: String, threadId?: String) => {
+    // A resend of a message we already have only needs the stored copy echoed.
+    const existing = frame.clientId && history.find((m) => m.from === sender.id && m.clientId === frame.clientId);
+    if (existing) {
+        sender.ws.send(JSON.stringify({ messageType: 'message', data: JSON.stringify(existing) }));
+        return;
+    }
+    const id = String(nextMessageId++);
+    messageOwners.set(id, sender.id);
+    const entry: any = {
+        id,
+        from: sender.id,
+        displayName: sender.nick,
+        message: text,
+        time: Date.now(),
+    };
+    if (threadId) {
+        entry.threadId = threadId;
+    }
+    if (frame.clientId) {
+        entry.clientId = frame.clientId;
+    }
+    // Disappearing messages leave the history once their time is up.
+    if (typeof frame.expiresAt === 'number' && frame.expiresAt > entry.time) {
+        entry.expiresAt = frame.expiresAt;
+    }
+    const attachments = attachmentUrls(frame.dataArray);
+    if (attachments.length > 0) {
+        entry.attachments = attachments;
+    }
+    history.push(entry);
+    if (history.length > HISTORY_LIMIT) {
+        history.shift();
+    }
+    broadcast(JSON.stringify({ messageType: 'message', data: JSON.stringify(entry) }));
+};
+
+// Attachments travel in a message frame's `dataArray`. Only http(s) URLs are
+// kept, and only the first few.
+const MAX_ATTACHMENTS = 10;
+const attachmentUrls = (urls: unknown): string[] =>
+    Array.isArray(urls)
+        ? urls.filter((u) => typeof u === 'string' && /^https?:\/\/\S+$/.test(u)).slice(0, MAX_ATTACHMENTS)
+        : [];
+
+// First byte of a compressed binary frame; see README.md.
+const COMPRESSED_MARKER = 0xc1;
+
+// Inverse of the client's LZSS compressor (Client/src/services/compression.rs).
+const decompress = (input: Buffer): string => {
+    let len = 0;
+    let pos = 0;
+    for (let shift = 0; ; shift += 7) {
+        const byte = input[pos++];
+        if (byte === undefined || shift > 28) {
+            throw new Error('compressed frame is truncated');
+        }
+        len += (byte & 0x7f) * 2 ** shift;
+        if ((byte & 0x80) === 0) {
+            break;
+        }
+    }
+    const out = Buffer.alloc(len);
+    let n = 0;
+    while (n < len && pos < input.length) {
+        const flags = input[pos++];
+        for (let bit = 0; bit < 8 && n < len; bit++) {
+            if ((flags & (1 << bit)) === 0) {
+                out[n++] = input[pos++];
+                continue;
+            }
+            const token = (input[pos] << 8) | input[pos + 1];
+            pos += 2;
+            const offset = (token >> 4) + 1;
+            if (offset > n) {
+                throw new Error('back-reference before the start of the frame');
+            }
+            for (let i = 0; i < (token & 0xf) + 3; i++, n++) {
+                out[n] = out[n - offset];
+            }
+        }
+    }
+    if (n !== len || pos > input.length) {
+        throw new Error('compressed frame is corrupt');
+    }
+    return out.toString();
+};
+
+// `clientId` names the rejected message or reply, so the client can stop
+// waiting for it.
+const sendError = (ws: WebSocket, code: string, clientId?: string) =>
+    ws.send(JSON.stringify({ messageType: 'error', data: JSON.stringify({ code, clientId }) }));
+
 const broadcast = (data: any) => {
     wss.clients.forEach((client) => {
         if (client.readyState === WebSocket.OPEN) {